@@ -0,0 +1,192 @@
+use crate::board::Vector;
+use crate::variant::Variant;
+use std::time::Duration;
+
+/*
+ * What has been written about a move, kept with the move in the MoveTree and written into the
+ * game's PGN after it, e.g. 12. Nxe5!? {the N can't be taken [%cal Gd1h5] [%clk 0:04:51]}
+ * the annotation on the tree's root is the comment before the first move
+ */
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Annotation {
+    pub comment: String,         // free text, empty if there is none
+    pub nags: Vec<u8>,           // numeric annotation glyphs, e.g. 1 for ! or 4 for ??, 0 for none
+    pub clock: Option<Duration>, // time left on the mover's clock after the move, [%clk]
+    pub arrows: Vec<Arrow>,      // [%cal]
+    pub squares: Vec<Highlight>, // [%csl]
+}
+
+// an arrow drawn from one square to another, in a colour such as 'G' for green
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arrow {
+    pub colour: char,
+    pub from: Vector,
+    pub to: Vector,
+}
+
+// a square coloured in, e.g. 'R' for red
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Highlight {
+    pub colour: char,
+    pub square: Vector,
+}
+
+// the glyphs for NAGs $1 to $6, which are written after the move rather than as numbers
+pub const GLYPHS: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+
+// reads a NAG written as a glyph, e.g. ?!, or as a number, e.g. $14
+pub fn nag(text: &str) -> Result<u8, &'static str> {
+    if let Some(index) = GLYPHS.iter().position(|glyph| *glyph == text) {
+        return Ok(index as u8 + 1);
+    }
+    match text.strip_prefix('$').map(str::parse::<u8>) {
+        Some(Ok(nag)) => Ok(nag),
+        _ => Err("NAGs are $0 to $255, or one of ! ? !! ?? !? ?!"),
+    }
+}
+
+impl Annotation {
+    pub fn is_empty(&self) -> bool {
+        *self == Annotation::default()
+    }
+    // the NAGs as written after the move, e.g. !? $14
+    pub fn glyphs(&self) -> String {
+        let mut text = String::new();
+        for nag in &self.nags {
+            match (*nag as usize).checked_sub(1).and_then(|i| GLYPHS.get(i)) {
+                Some(glyph) if text.is_empty() => text.push_str(glyph),
+                _ => text.push_str(&format!(" ${nag}")),
+            }
+        }
+        text
+    }
+    // the comment with its commands, in braces, None if there is nothing to write
+    pub fn comment_text(&self, variant: Variant) -> Option<String> {
+        let square = |pos: &Vector| variant.notation(pos).unwrap_or_default();
+        let mut parts = Vec::new();
+        if !self.comment.is_empty() {
+            parts.push(self.comment.clone());
+        }
+        if !self.arrows.is_empty() {
+            let arrows: Vec<String> = self
+                .arrows
+                .iter()
+                .map(|arrow| {
+                    format!(
+                        "{}{}{}",
+                        arrow.colour,
+                        square(&arrow.from),
+                        square(&arrow.to)
+                    )
+                })
+                .collect();
+            parts.push(format!("[%cal {}]", arrows.join(",")));
+        }
+        if !self.squares.is_empty() {
+            let squares: Vec<String> = self
+                .squares
+                .iter()
+                .map(|highlight| format!("{}{}", highlight.colour, square(&highlight.square)))
+                .collect();
+            parts.push(format!("[%csl {}]", squares.join(",")));
+        }
+        if let Some(clock) = self.clock {
+            parts.push(format!("[%clk {}]", write_clock(clock)));
+        }
+        match parts.is_empty() {
+            true => None,
+            false => Some(format!("{{{}}}", parts.join(" "))),
+        }
+    }
+    /*
+     * adds a comment read from PGN, without its braces, taking out the commands it knows
+     * anything else, including other commands such as [%eval], is kept in the text
+     */
+    pub fn read_comment(&mut self, text: &str, variant: Variant) -> Result<(), &'static str> {
+        let mut rest = String::new();
+        let mut text = text;
+        while let Some(start) = text.find("[%") {
+            rest.push_str(&text[..start]);
+            let end = start + text[start..].find(']').ok_or("unclosed [% command")?;
+            let command = &text[start + 2..end];
+            let (name, value) = command.split_once(' ').unwrap_or((command, ""));
+            let value = value.trim();
+            match name {
+                "clk" => self.clock = Some(read_clock(value)?),
+                "cal" => {
+                    for arrow in value.split(',').filter(|arrow| !arrow.is_empty()) {
+                        let (colour, squares) = split_colour(arrow)?;
+                        let middle = (1..squares.len())
+                            .find(|i| {
+                                let bytes = squares.as_bytes();
+                                bytes[*i].is_ascii_alphabetic() && bytes[i - 1].is_ascii_digit()
+                            })
+                            .ok_or("arrows join two squares, e.g. Ge2e4")?;
+                        self.arrows.push(Arrow {
+                            colour,
+                            from: variant.square(&squares[..middle])?,
+                            to: variant.square(&squares[middle..])?,
+                        });
+                    }
+                }
+                "csl" => {
+                    for highlight in value.split(',').filter(|square| !square.is_empty()) {
+                        let (colour, square) = split_colour(highlight)?;
+                        self.squares.push(Highlight {
+                            colour,
+                            square: variant.square(square)?,
+                        });
+                    }
+                }
+                _ => rest.push_str(&text[start..=end]),
+            }
+            text = &text[end + 1..];
+        }
+        rest.push_str(text);
+        let rest = rest.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !rest.is_empty() {
+            if !self.comment.is_empty() {
+                self.comment.push(' ');
+            }
+            self.comment.push_str(&rest);
+        }
+        Ok(())
+    }
+}
+
+// the colour letter an arrow or highlight starts with, and the rest
+fn split_colour(text: &str) -> Result<(char, &str), &'static str> {
+    let mut chars = text.trim().chars();
+    match chars.next() {
+        Some(colour) if colour.is_ascii_uppercase() => Ok((colour, chars.as_str())),
+        _ => Err("arrows and highlights start with a colour, e.g. R, G, B or Y"),
+    }
+}
+
+// reads a clock written as h:mm:ss, with optional fractions of a second, e.g. 0:04:51.3
+pub fn read_clock(text: &str) -> Result<Duration, &'static str> {
+    let mut seconds = 0.0;
+    for part in text.split(':') {
+        let part: f64 = part.parse().map_err(|_| "clocks are written h:mm:ss")?;
+        seconds = seconds * 60.0 + part;
+    }
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err("clocks are written h:mm:ss");
+    }
+    Ok(Duration::from_millis((seconds * 1000.0).round() as u64))
+}
+
+// inverse of read_clock
+pub fn write_clock(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    let fraction = match clock.subsec_millis() {
+        0 => String::new(),
+        millis if millis % 100 == 0 => format!(".{}", millis / 100),
+        millis => format!(".{millis:03}"),
+    };
+    format!("{hours}:{minutes:02}:{seconds:02}{fraction}")
+}
@@ -1,19 +1,22 @@
-use crate::piece::Piece;
+use crate::piece::{Piece, PieceKind, PlayerId};
 use std::fmt;
 use std::ops::{Add, AddAssign, Index, IndexMut, Mul};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Matrix<T>(pub Vec<Vec<T>>);
 
 #[derive(Clone)]
-pub struct Board(pub Matrix<Piece>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Board(pub Matrix<Option<Piece>>); // None for empty squares
 
 // for indexing into Board as an (x, y) ordered pair
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector(pub i32, pub i32);
 
 impl Index<&Vector> for Board {
-    type Output = Piece;
+    type Output = Option<Piece>;
 
     fn index(&self, pos: &Vector) -> &Self::Output {
         &self.0[pos]
@@ -44,6 +47,15 @@ impl<T: Clone> Matrix<T> {
     pub fn new(val: T, rows: usize, cols: usize) -> Matrix<T> {
         Matrix(vec![vec![val; cols]; rows])
     }
+    // copy of the columns in range, e.g. one level of a three dimensional board
+    pub fn columns(&self, range: std::ops::Range<usize>) -> Matrix<T> {
+        Matrix(
+            self.0
+                .iter()
+                .map(|row| row[range.clone()].to_vec())
+                .collect(),
+        )
+    }
 }
 
 impl<T> Matrix<T> {
@@ -61,7 +73,7 @@ impl fmt::Display for Matrix<bool> {
                 let print = if *col { '1' } else { '0' };
                 write!(f, "{print} ")?;
             }
-            write!(f, "|\n")?;
+            writeln!(f, "|")?;
         }
         write!(f, "")
     }
@@ -92,73 +104,109 @@ impl Mul<i32> for Vector {
 
 impl Board {
     pub fn new(rows: usize, cols: usize) -> Board {
-        Board(Matrix(vec![
-            vec![
-                Piece {
-                    id: ' ',
-                    owner: 0,
-                    has_moved: false
-                };
-                cols
-            ];
-            rows
-        ]))
+        Board(Matrix::new(None, rows, cols))
     }
     pub fn standard() -> Board {
         let mut board = Self::new(8, 8);
-        let pieces = ['R', 'N', 'B', 'Q', 'K', 'B', 'N', 'R'];
-        for (i, id) in pieces.into_iter().enumerate() {
+        let pieces = [
+            PieceKind::Rook,
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Queen,
+            PieceKind::King,
+            PieceKind::Bishop,
+            PieceKind::Knight,
+            PieceKind::Rook,
+        ];
+        for (i, kind) in pieces.into_iter().enumerate() {
             // fill 8th and 1st rank
-            board.set(
-                Piece {
-                    id,
-                    owner: 2,
-                    has_moved: false,
-                },
-                7,
-                i,
-            );
-            board.set(
-                Piece {
-                    id,
-                    owner: 1,
-                    has_moved: false,
-                },
-                0,
-                i,
-            );
+            board.set(Piece::new(kind, PlayerId(2)), 7, i);
+            board.set(Piece::new(kind, PlayerId(1)), 0, i);
         }
         for i in 0..8 {
             // fill 7th and 2nd rank
-            board.set(
-                Piece {
-                    id: 'P',
-                    owner: 2,
-                    has_moved: false,
-                },
-                6,
-                i,
-            );
-            board.set(
-                Piece {
-                    id: 'P',
-                    owner: 1,
-                    has_moved: false,
-                },
-                1,
-                i,
-            );
+            board.set(Piece::new(PieceKind::Pawn, PlayerId(2)), 6, i);
+            board.set(Piece::new(PieceKind::Pawn, PlayerId(1)), 1, i);
         }
         board
     }
+    /*
+     * parses the piece placement field of a FEN string, ranks may be wider than 8 files
+     * uppercase pieces belong to player 1, lowercase pieces to player 2
+     */
+    pub fn from_fen(placement: &str) -> Result<Board, &'static str> {
+        let mut rows = Vec::new();
+        for rank in placement.split('/') {
+            let mut row: Vec<Option<Piece>> = Vec::new();
+            let mut empty = 0;
+            let mut promoted = false;
+            for c in rank.chars() {
+                if let Some(digit) = c.to_digit(10) {
+                    // runs of empty squares can be multiple digits on large boards, e.g. "10"
+                    empty = empty * 10 + digit as usize;
+                    continue;
+                }
+                // duck chess marks the duck with *
+                if c == '*' {
+                    row.extend(vec![None; empty]);
+                    empty = 0;
+                    row.push(Some(Piece::duck()));
+                    continue;
+                }
+                // crazyhouse marks promoted pieces with a trailing ~
+                if c == '~' {
+                    match row.last_mut() {
+                        Some(Some(piece)) if empty == 0 => piece.promoted = true,
+                        _ => return Err("~ must follow a piece"),
+                    }
+                    continue;
+                }
+                // boards which aren't rectangles mark their holes with -
+                if c == '-' {
+                    row.extend(vec![None; empty]);
+                    empty = 0;
+                    row.push(Some(Piece::hole()));
+                    continue;
+                }
+                // shogi marks promoted pieces with a leading +
+                if c == '+' {
+                    promoted = true;
+                    continue;
+                }
+                if !c.is_ascii_alphabetic() {
+                    return Err("unexpected character in FEN piece placement");
+                }
+                row.extend(vec![None; empty]);
+                empty = 0;
+                let owner = PlayerId(if c.is_ascii_uppercase() { 1 } else { 2 });
+                let mut piece = Piece::new(PieceKind::from_char(c.to_ascii_uppercase()), owner);
+                piece.promoted = promoted;
+                row.push(Some(piece));
+                promoted = false;
+            }
+            if promoted {
+                return Err("+ must come before a piece");
+            }
+            row.extend(vec![None; empty]);
+            rows.push(row);
+        }
+        if rows[0].is_empty() || rows.iter().any(|row| row.len() != rows[0].len()) {
+            return Err("FEN ranks must all have the same nonzero width");
+        }
+        // FEN lists the last rank first
+        rows.reverse();
+        Ok(Board(Matrix(rows)))
+    }
     pub fn set(&mut self, piece: Piece, row: usize, col: usize) {
-        self.0 .0[row][col] = piece;
+        self.0 .0[row][col] = Some(piece);
     }
+    // holes in the board count as out of bounds
     pub fn in_bounds(&self, pos: &Vector) -> bool {
         pos.0 >= 0
             && pos.1 >= 0
             && (pos.0 as usize) < self.0 .0[0].len()
             && (pos.1 as usize) < self.0 .0.len()
+            && !self[pos].as_ref().is_some_and(Piece::is_hole)
     }
     /*
      * (rows, cols)
@@ -167,34 +215,155 @@ impl Board {
         self.0.shape()
     }
     pub fn draw(&self, highlighting: &Matrix<bool>) {
-        for (row, hrow) in self.0 .0.iter().rev().zip(highlighting.0.iter().rev()) {
+        let (rows, cols) = self.shape();
+        self.draw_visible(highlighting, &Matrix::new(true, rows, cols));
+    }
+    // same as draw, but squares which aren't visible are hatched over
+    pub fn draw_visible(&self, highlighting: &Matrix<bool>, visible: &Matrix<bool>) {
+        self.draw_squares(highlighting, visible, false);
+    }
+    // same as draw, but promoted pieces are marked with a leading +, as in shogi
+    pub fn draw_promotions(&self, highlighting: &Matrix<bool>) {
+        let (rows, cols) = self.shape();
+        self.draw_squares(highlighting, &Matrix::new(true, rows, cols), true);
+    }
+    // holes are hatched with /, squares which aren't visible with #
+    fn draw_squares(&self, highlighting: &Matrix<bool>, visible: &Matrix<bool>, promotions: bool) {
+        let rows = self.0 .0.iter().rev();
+        let masks = highlighting.0.iter().rev().zip(visible.0.iter().rev());
+        for (row, (hrow, vrow)) in rows.zip(masks) {
             for _col in row {
                 print!("+-----");
             }
-            print!("+\n");
-            for hcol in hrow {
+            println!("+");
+            for (col, (hcol, vcol)) in row.iter().zip(hrow.iter().zip(vrow)) {
                 let highlight = if *hcol { '*' } else { ' ' };
-                print!("| {highlight} {highlight} ");
+                match (vcol, col.as_ref().is_some_and(Piece::is_hole)) {
+                    (false, _) => print!("|#####"),
+                    (true, true) => print!("|/////"),
+                    (true, false) => print!("| {highlight} {highlight} "),
+                }
             }
-            print!("|\n");
-            for col in row {
-                if col.id == ' ' {
-                    print!("|     ");
-                } else {
-                    print!("| {}:{} ", col.id, col.owner);
+            println!("|");
+            for (col, vcol) in row.iter().zip(vrow) {
+                match col {
+                    _ if !vcol => print!("|#####"),
+                    Some(piece) if piece.is_hole() => print!("|/////"),
+                    None => print!("|     "),
+                    Some(piece) if promotions && piece.promoted => {
+                        print!("|+{}:{} ", piece.kind, piece.owner)
+                    }
+                    Some(piece) => print!("| {}:{} ", piece.kind, piece.owner),
                 }
             }
-            print!("|\n");
-            for hcol in hrow {
+            println!("|");
+            for (col, (hcol, vcol)) in row.iter().zip(hrow.iter().zip(vrow)) {
                 let highlight = if *hcol { '*' } else { ' ' };
-                print!("| {highlight} {highlight} ");
+                match (vcol, col.as_ref().is_some_and(Piece::is_hole)) {
+                    (false, _) => print!("|#####"),
+                    (true, true) => print!("|/////"),
+                    (true, false) => print!("| {highlight} {highlight} "),
+                }
             }
-            print!("|\n");
+            println!("|");
         }
         for _col in &self.0 .0[0] {
             print!("+-----");
         }
-        print!("+\n")
+        println!("+")
+    }
+    /*
+     * same as draw, but each square is drawn as a hexagon, for boards stored in axial coordinates
+     * the hexagons in each column are half a hexagon higher than those in the column to the left
+     */
+    pub fn draw_hex(&self, highlighting: &Matrix<bool>) {
+        let (rows, cols) = self.shape();
+        // line of the top edge of each hexagon, counted from the top of the picture
+        let top = |pos: &Vector| (2 * rows + cols) as i32 - 2 * pos.1 - pos.0 - 3;
+        let mut canvas = vec![vec![' '; 4 * cols + 1]; 2 * rows + cols];
+        for row in 0..rows as i32 {
+            for col in 0..cols as i32 {
+                let pos = Vector(col, row);
+                if !self.in_bounds(&pos) {
+                    continue;
+                }
+                let label = match (&self[&pos], highlighting[&pos]) {
+                    (None, false) => String::from("   "),
+                    (None, true) => String::from(" * "),
+                    (Some(piece), false) => format!("{}:{}", piece.kind, piece.owner),
+                    (Some(piece), true) => format!("{}*{}", piece.kind, piece.owner),
+                };
+                let (line, x) = (top(&pos) as usize, 4 * col as usize);
+                let shape = [
+                    " ___ ".to_string(),
+                    format!("/{label}\\"),
+                    "\\___/".to_string(),
+                ];
+                for (i, text) in shape.iter().enumerate() {
+                    for (j, c) in text.chars().enumerate() {
+                        // neighbouring hexagons share edges, so don't paint over them with spaces
+                        if c != ' ' || i == 1 {
+                            canvas[line + i][x + j] = c;
+                        }
+                    }
+                }
+            }
+        }
+        for line in canvas {
+            let line: String = line.into_iter().collect();
+            if !line.trim().is_empty() {
+                println!("{}", line.trim_end());
+            }
+        }
+    }
+    /*
+     * same as draw, for boards made of levels 'width' columns wide placed side by side,
+     * each starting 'stride' columns after the last, as in cube
+     * each level is drawn as its own board, lettered from A, with the top level first
+     */
+    pub fn draw_levels(&self, highlighting: &Matrix<bool>, width: usize, stride: usize) {
+        let (_, cols) = self.shape();
+        let levels = (cols - width) / stride + 1;
+        for level in (0..levels).rev() {
+            let range = level * stride..level * stride + width;
+            println!("Level {}", (b'A' + level as u8) as char);
+            Board(self.0.columns(range.clone())).draw(&highlighting.columns(range));
+        }
+    }
+    /*
+     * same as draw, but pieces stand on the points where lines cross, as in xiangqi
+     * a river is drawn across the middle of the board, which only the edge files cross
+     */
+    pub fn draw_intersections(&self, highlighting: &Matrix<bool>) {
+        let (rows, cols) = self.shape();
+        let points = self
+            .0
+             .0
+            .iter()
+            .zip(highlighting.0.iter())
+            .enumerate()
+            .rev();
+        for (row, (prow, hrow)) in points {
+            let labels: Vec<String> = prow
+                .iter()
+                .zip(hrow)
+                .map(|(point, highlight)| match (point, highlight) {
+                    (None, false) => String::from("-+-"),
+                    (None, true) => String::from("-*-"),
+                    (Some(piece), false) => format!("{}:{}", piece.kind, piece.owner),
+                    (Some(piece), true) => format!("{}*{}", piece.kind, piece.owner),
+                })
+                .collect();
+            println!("{}", labels.join("--"));
+            if row == 0 {
+                break;
+            }
+            if row == rows / 2 {
+                println!(" | {} | ", "~".repeat(cols * 5 - 8));
+            } else {
+                println!("{}", vec![" | "; cols].join("  "));
+            }
+        }
     }
 }
 
@@ -204,24 +373,27 @@ impl fmt::Display for Board {
             for _col in row {
                 write!(f, "+-----")?;
             }
-            write!(f, "+\n")?;
+            writeln!(f, "+")?;
             for _col in row {
                 write!(f, "|     ")?;
             }
-            write!(f, "|\n")?;
+            writeln!(f, "|")?;
             for col in row {
-                write!(f, "| {}:{} ", col.id, col.owner)?;
+                match col {
+                    Some(piece) => write!(f, "| {}:{} ", piece.kind, piece.owner)?,
+                    None => write!(f, "|  :0 ")?,
+                }
             }
-            write!(f, "|\n")?;
+            writeln!(f, "|")?;
             for _col in row {
                 write!(f, "|     ")?;
             }
-            write!(f, "|\n")?;
+            writeln!(f, "|")?;
         }
         for _col in &self.0 .0[0] {
             write!(f, "+-----")?;
         }
-        write!(f, "+\n")
+        writeln!(f, "+")
     }
 }
 
@@ -234,4 +406,17 @@ impl Vector {
         let rank = self.1 + 1;
         Ok(format!("{}{}", file, rank))
     }
+    // inverse of to_notation, ranks may have more than one digit (e.g. "j10")
+    pub fn from_notation(notation: &str) -> Result<Vector, &'static str> {
+        let mut chars = notation.chars();
+        let file = match chars.next() {
+            Some(c @ 'a'..='z') => c as i32 - 'a' as i32,
+            _ => return Err("file must be a lowercase letter"),
+        };
+        let rank: i32 = match chars.as_str().parse() {
+            Ok(rank) if rank >= 1 => rank,
+            _ => return Err("rank must be a positive number"),
+        };
+        Ok(Vector(file, rank - 1))
+    }
 }
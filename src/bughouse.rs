@@ -0,0 +1,66 @@
+use crate::board::Vector;
+use crate::game::Game;
+use crate::piece::PieceKind;
+use crate::variant::Variant;
+
+/*
+ * Two linked games played by two teams of two
+ * player 1 on one board is partnered with player 2 on the other board, so a piece captured
+ * from player n on one board is handed to player n on the other board
+ */
+pub struct Bughouse {
+    boards: [Game; 2], // only changed through Bughouse, so captures always reach the partner
+}
+
+impl Bughouse {
+    pub fn new() -> Bughouse {
+        Bughouse {
+            boards: [
+                Game::from_variant(Variant::Bughouse),
+                Game::from_variant(Variant::Bughouse),
+            ],
+        }
+    }
+    // the game on board 'board', 0 or 1
+    pub fn board(&self, board: usize) -> &Game {
+        &self.boards[board]
+    }
+    // same as Game::set_promotion on the given board
+    pub fn set_promotion(&mut self, board: usize, promotion: Option<PieceKind>) -> bool {
+        self.boards[board].set_promotion(promotion)
+    }
+    // same as Game::select on the given board
+    pub fn select(&mut self, board: usize, pos: Vector) {
+        let captured = self.boards[board].state.captured.len();
+        self.boards[board].select(pos);
+        self.pass_captures(board, captured);
+    }
+    // same as Game::drop_piece on the given board
+    pub fn drop_piece(&mut self, board: usize, kind: PieceKind, pos: &Vector) -> bool {
+        self.boards[board].drop_piece(kind, pos)
+    }
+    // hands every piece captured on 'board' after the first 'since' captures to the partner
+    fn pass_captures(&mut self, board: usize, since: usize) {
+        let captured = self.boards[board].state.captured[since..].to_vec();
+        for piece in captured {
+            let kind = if piece.promoted {
+                PieceKind::Pawn
+            } else {
+                piece.kind
+            };
+            self.boards[1 - board].add_to_hand(piece.owner, kind);
+        }
+    }
+    pub fn draw(&self) {
+        for (i, game) in self.boards.iter().enumerate() {
+            println!("board {}:", i + 1);
+            game.draw();
+        }
+    }
+}
+
+impl Default for Bughouse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,112 @@
+use crate::board::Vector;
+
+/*
+ * Raumschach's 5x5x5 board, stored as its five levels side by side in a 5x33 Matrix
+ * the cell (x, y, z) is stored at Vector(x + 7 * z, y), and the two columns between
+ * neighbouring levels are holes, so a step off the side of one level never lands on another
+ * levels are lettered A (bottom) to E (top), so a cell is written like Cc3
+ */
+pub const SIZE: i32 = 5;
+pub const STRIDE: i32 = SIZE + 2; // columns from one level to the next, wider than any step
+
+// for indexing into the cube as an (x, y, z) triple, z being the level
+#[derive(Clone, PartialEq, Debug)]
+pub struct Vector3(pub i32, pub i32, pub i32);
+
+impl Vector3 {
+    // where a cell is stored in the Matrix, also turns steps between cells into Matrix steps
+    pub fn flatten(&self) -> Vector {
+        Vector(self.0 + STRIDE * self.2, self.1)
+    }
+    // inverse of flatten, None for the holes between levels
+    pub fn unflatten(pos: &Vector) -> Option<Vector3> {
+        let (x, z) = (pos.0.rem_euclid(STRIDE), pos.0.div_euclid(STRIDE));
+        let cell = Vector3(x, pos.1, z);
+        cell.on_board().then_some(cell)
+    }
+    pub fn on_board(&self) -> bool {
+        [self.0, self.1, self.2]
+            .iter()
+            .all(|i| (0..SIZE).contains(i))
+    }
+    pub fn to_notation(&self) -> Result<String, &'static str> {
+        if !self.on_board() {
+            return Err("not a cell of the cube");
+        }
+        let level = (self.2 as u8 + b'A') as char;
+        let file = (self.0 as u8 + b'a') as char;
+        Ok(format!("{}{}{}", level, file, self.1 + 1))
+    }
+    // inverse of to_notation, e.g. "Ee5"
+    pub fn from_notation(notation: &str) -> Result<Vector3, &'static str> {
+        let mut chars = notation.chars();
+        let level = match chars.next() {
+            Some(c @ 'A'..='E') => c as i32 - 'A' as i32,
+            _ => return Err("level must be a letter from A to E"),
+        };
+        let file = match chars.next() {
+            Some(c @ 'a'..='e') => c as i32 - 'a' as i32,
+            _ => return Err("file must be a letter from a to e"),
+        };
+        match chars.as_str().parse::<i32>() {
+            Ok(rank @ 1..=SIZE) => Ok(Vector3(file, rank - 1, level)),
+            _ => Err("rank must be a number from 1 to 5"),
+        }
+    }
+}
+
+// Matrix steps for every (x, y, z) step whose sorted distances along each axis are 'shape'
+fn steps(shape: [i32; 3]) -> Vec<Vector> {
+    let mut steps = Vec::new();
+    for x in -2..=2 {
+        for y in -2..=2 {
+            for z in -2..=2 {
+                let mut distances = [x, y, z].map(i32::abs);
+                distances.sort();
+                if distances == shape {
+                    steps.push(Vector3(x, y, z).flatten());
+                }
+            }
+        }
+    }
+    steps
+}
+
+// through the 6 faces of a cell, as R
+pub fn orthogonals() -> Vec<Vector> {
+    steps([0, 0, 1])
+}
+// through the 12 edges of a cell, as B
+pub fn diagonals() -> Vec<Vector> {
+    steps([0, 1, 1])
+}
+// through the 8 corners of a cell, as the unicorn
+pub fn triagonals() -> Vec<Vector> {
+    steps([1, 1, 1])
+}
+// two steps along one axis and one along another, as N
+pub fn knight_offsets() -> Vec<Vector> {
+    steps([0, 1, 2])
+}
+
+/*
+ * a P moves forwards, or upwards towards the enemy's levels, and captures one step
+ * diagonally forwards or upwards: the bishop steps which go forwards or upwards and not back
+ */
+pub fn pawn_moves(direction: &Vector) -> [Vector; 2] {
+    let forwards = direction.1;
+    [
+        Vector3(0, forwards, 0).flatten(),
+        Vector3(0, 0, forwards).flatten(),
+    ]
+}
+pub fn pawn_captures(direction: &Vector) -> [Vector; 5] {
+    let forwards = direction.1;
+    [
+        Vector3(1, forwards, 0).flatten(),
+        Vector3(-1, forwards, 0).flatten(),
+        Vector3(1, 0, forwards).flatten(),
+        Vector3(-1, 0, forwards).flatten(),
+        Vector3(0, forwards, forwards).flatten(),
+    ]
+}
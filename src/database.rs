@@ -0,0 +1,282 @@
+use crate::encoding::{self, Reader, Writer};
+use crate::game::{Game, GameState};
+use crate::pgn::{self, Tags};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/*
+ * A collection of games kept in a file, in the binary format of encoding, searchable by tags,
+ * by positions reached, and by material, e.g.
+ *
+ *     let mut database = Database::open("games.rcg")?;
+ *     database.import_pgn(&fs::read_to_string("morphy.pgn")?)?;
+ *     let games = database.with_position(&game.state);
+ *
+ * every position of every line is indexed, variations included, when the file is opened
+ * games are numbered from 0 in the order they were imported
+ */
+pub struct Database {
+    path: Option<PathBuf>, // None for a database kept only in memory, see from_pgn
+    games: Vec<Entry>,
+    positions: HashMap<u64, Vec<(usize, Option<String>)>>, // games reaching each position, and the move played next
+}
+
+// what is kept in memory about each game
+struct Entry {
+    record: Vec<u8>, // see encoding
+    tags: Tags,
+    result: String,             // 1-0, 0-1, 1/2-1/2 or *
+    materials: HashSet<String>, // every material signature reached, see material
+}
+
+// games matching every field which is set, text matches ignore case
+#[derive(Clone, Default, Debug)]
+pub struct Query {
+    pub player: Option<String>, // part of the White or Black tag
+    pub event: Option<String>,  // part of the Event tag
+    pub date: Option<String>,   // the start of the Date tag, e.g. 1858 or 1858.10
+    pub result: Option<String>, // 1-0, 0-1, 1/2-1/2 or *
+}
+
+// how often a move was played from a position, and how those games ended
+#[derive(Clone, PartialEq, Debug)]
+pub struct MoveStats {
+    pub san: String,
+    pub games: usize,
+    pub wins: usize, // won by player 1
+    pub draws: usize,
+    pub losses: usize, // won by player 2
+}
+
+impl Database {
+    // opens the database at 'path', creating an empty one if there is none
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Database> {
+        let path = path.as_ref().to_path_buf();
+        if !path.exists() {
+            Writer::new(fs::File::create(&path)?)?;
+        }
+        let mut reader = Reader::new(fs::File::open(&path)?)?;
+        let mut database = Database {
+            path: Some(path),
+            games: Vec::new(),
+            positions: HashMap::new(),
+        };
+        while let Some(record) = reader.read_record()? {
+            let game = encoding::decode(&record)?;
+            database.index(&game, record);
+        }
+        Ok(database)
+    }
+    // a database of the games in a PGN file, kept in memory rather than written anywhere
+    pub fn from_pgn(text: &str) -> io::Result<Database> {
+        let mut database = Database {
+            path: None,
+            games: Vec::new(),
+            positions: HashMap::new(),
+        };
+        database.import_pgn(text)?;
+        Ok(database)
+    }
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+    /*
+     * adds every game in a PGN file, returning how many there were
+     * nothing is added if any of them can't be read
+     */
+    pub fn import_pgn(&mut self, text: &str) -> io::Result<usize> {
+        let mut games = Vec::new();
+        for (number, game) in pgn::split(text).into_iter().enumerate() {
+            let game = pgn::read(game).map_err(|err| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("game {}: {err}", number + 1),
+                )
+            })?;
+            games.push(game);
+        }
+        if let Some(path) = &self.path {
+            let mut writer = Writer::resume(OpenOptions::new().append(true).open(path)?);
+            for game in &games {
+                writer.write_game(game)?;
+            }
+        }
+        for game in &games {
+            self.index(game, encoding::encode(game)?);
+        }
+        Ok(games.len())
+    }
+    fn index(&mut self, game: &Game, record: Vec<u8>) {
+        let id = self.games.len();
+        let tree = game.tree();
+        let mut materials = HashSet::new();
+        for node in tree.preorder().map(|node| tree.node(node).unwrap()) {
+            materials.insert(material(&node.state));
+            let games = self
+                .positions
+                .entry(position_hash(&node.state))
+                .or_default();
+            if node.children.is_empty() {
+                games.push((id, None));
+            }
+            for child in &node.children {
+                games.push((id, tree.node(*child).unwrap().san.clone()));
+            }
+        }
+        self.games.push(Entry {
+            record,
+            tags: game.tags.clone(),
+            result: pgn::result(game).to_string(),
+            materials,
+        });
+    }
+    // the game numbered 'id', None if there isn't one
+    pub fn game(&self, id: usize) -> Option<io::Result<Game>> {
+        let entry = self.games.get(id)?;
+        Some(encoding::decode(&entry.record))
+    }
+    // the value of the tag 'name' of game 'id', e.g. White
+    pub fn tag(&self, id: usize, name: &str) -> Option<&str> {
+        pgn::tag(&self.games.get(id)?.tags, name)
+    }
+    pub fn result(&self, id: usize) -> Option<&str> {
+        Some(&self.games.get(id)?.result)
+    }
+    pub fn search(&self, query: &Query) -> Vec<usize> {
+        let contains = |tags: &Tags, names: &[&str], part: &Option<String>| match part {
+            Some(part) => names.iter().any(|name| {
+                let value = pgn::tag(tags, name).unwrap_or("");
+                value.to_lowercase().contains(&part.to_lowercase())
+            }),
+            None => true,
+        };
+        let matches = |entry: &Entry| {
+            let date = pgn::tag(&entry.tags, "Date").unwrap_or("");
+            contains(&entry.tags, &["White", "Black"], &query.player)
+                && contains(&entry.tags, &["Event"], &query.event)
+                && query
+                    .date
+                    .as_ref()
+                    .is_none_or(|start| date.starts_with(start.as_str()))
+                && query
+                    .result
+                    .as_ref()
+                    .is_none_or(|result| *result == entry.result)
+        };
+        (0..self.games.len())
+            .filter(|id| matches(&self.games[*id]))
+            .collect()
+    }
+    // games reaching the position in 'state', in any line
+    pub fn with_position(&self, state: &GameState) -> Vec<usize> {
+        self.with_hash(position_hash(state))
+    }
+    // games reaching the position with the hash 'hash', see position_hash
+    pub fn with_hash(&self, hash: u64) -> Vec<usize> {
+        let mut games: Vec<usize> = match self.positions.get(&hash) {
+            Some(games) => games.iter().map(|(id, _)| *id).collect(),
+            None => Vec::new(),
+        };
+        games.dedup();
+        games
+    }
+    // games reaching the material signature 'signature' at some point, e.g. KRPvKR, see material
+    pub fn with_material(&self, signature: &str) -> Vec<usize> {
+        let signature = normalise_material(signature);
+        let reached = |entry: &Entry| entry.materials.contains(&signature);
+        (0..self.games.len())
+            .filter(|id| reached(&self.games[*id]))
+            .collect()
+    }
+    // the moves played from the position in 'state', most played first, each counted once a game
+    pub fn move_stats(&self, state: &GameState) -> Vec<MoveStats> {
+        let mut played: Vec<(usize, &String)> = match self.positions.get(&position_hash(state)) {
+            Some(games) => games
+                .iter()
+                .filter_map(|(id, san)| Some((*id, san.as_ref()?)))
+                .collect(),
+            None => Vec::new(),
+        };
+        played.sort();
+        played.dedup();
+        let mut stats: Vec<MoveStats> = Vec::new();
+        for (id, san) in played {
+            let index = match stats.iter().position(|stat| stat.san == *san) {
+                Some(index) => index,
+                None => {
+                    stats.push(MoveStats {
+                        san: san.clone(),
+                        games: 0,
+                        wins: 0,
+                        draws: 0,
+                        losses: 0,
+                    });
+                    stats.len() - 1
+                }
+            };
+            let stat = &mut stats[index];
+            stat.games += 1;
+            match self.games[id].result.as_str() {
+                "1-0" => stat.wins += 1,
+                "0-1" => stat.losses += 1,
+                "1/2-1/2" => stat.draws += 1,
+                _ => {}
+            }
+        }
+        stats.sort_by(|a, b| b.games.cmp(&a.games).then_with(|| a.san.cmp(&b.san)));
+        stats
+    }
+}
+
+/*
+ * a number telling positions apart, from the variant, the pieces on each square and in each hand
+ * and the player to move, the same from one run of the program to the next
+ */
+pub fn position_hash(state: &GameState) -> u64 {
+    let key = format!(
+        "{} {} {}",
+        state.variant.name(),
+        state.position_key(),
+        state.turn()
+    );
+    // FNV-1a
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// the order pieces are written in a material signature, any others come after, alphabetically
+const MATERIAL_ORDER: &str = "KQRBNP";
+
+/*
+ * the pieces each player has on the board, players separated by v, e.g. KRPvKR
+ * the duck and holes in the board belong to no one and aren't counted
+ */
+pub fn material(state: &GameState) -> String {
+    let mut sides = vec![String::new(); state.num_players()];
+    for piece in state.board.0 .0.iter().flatten().flatten() {
+        if let Some(index) = piece.owner.index() {
+            sides[index].push(piece.kind.to_char());
+        }
+    }
+    normalise_material(&sides.join("v"))
+}
+
+// a material signature with each side's pieces in order, so KPRvRK reads KRPvKR
+fn normalise_material(signature: &str) -> String {
+    let rank = |c: &char| (MATERIAL_ORDER.find(*c).unwrap_or(MATERIAL_ORDER.len()), *c);
+    let sides: Vec<String> = signature
+        .split('v')
+        .map(|side| {
+            let mut pieces: Vec<char> = side.trim().chars().collect();
+            pieces.sort_by_key(rank);
+            pieces.into_iter().collect()
+        })
+        .collect();
+    sides.join("v")
+}
@@ -0,0 +1,431 @@
+use crate::annotation::{Annotation, Arrow, Highlight};
+use crate::board::Vector;
+use crate::game::{Game, GameState};
+use crate::outcome::{Outcome, REASONS};
+use crate::pgn::{self, Tags};
+use crate::piece::PlayerId;
+use crate::tree::{MoveTree, NodeId};
+use crate::variant::Variant;
+use std::io::{self, ErrorKind, Read, Write};
+use std::time::Duration;
+
+/*
+ * A compact binary format for storing many games, with every variation and annotation
+ *
+ * a stream starts with MAGIC, then each game is its length in bytes and a record of
+ *   - a header, the variant's name, the result, how and where the game was ended if it was
+ *     ended by Game::finish, and the tags
+ *   - the move tree, each move written as its index in Game::move_list, in as few bits as the
+ *     number of moves to choose from needs, so a forced move takes none, and most moves under a byte
+ *
+ * the tree is written from the root, every move followed by the moves after it, with a bit before
+ * each move saying there is one, then the position the game was at
+ * games must start from their variant's position, or the one in their FEN tag
+ */
+
+// the bytes a stream starts with, the last is the version of the format
+pub const MAGIC: [u8; 4] = *b"RCG\x02";
+
+const RESULTS: [&str; 4] = ["*", "1-0", "0-1", "1/2-1/2"];
+
+// writes games one after another to a stream
+pub struct Writer<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(mut inner: W) -> io::Result<Writer<W>> {
+        inner.write_all(&MAGIC)?;
+        Ok(Writer { inner })
+    }
+    // carries on a stream which already has games in it, e.g. a file opened to append to
+    pub fn resume(inner: W) -> Writer<W> {
+        Writer { inner }
+    }
+    pub fn write_game(&mut self, game: &Game) -> io::Result<()> {
+        let record = encode(game)?;
+        let mut length = Bits::default();
+        length.varint(record.len());
+        self.inner.write_all(&length.bytes)?;
+        self.inner.write_all(&record)
+    }
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+// reads the games in a stream written by Writer, one at a time or as an iterator
+pub struct Reader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(mut inner: R) -> io::Result<Reader<R>> {
+        let mut magic = [0; 4];
+        inner.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid("not a game archive"));
+        }
+        Ok(Reader { inner })
+    }
+    // the next game, None at the end of the stream
+    pub fn read_game(&mut self) -> io::Result<Option<Game>> {
+        match self.read_record()? {
+            Some(record) => decode(&record).map(Some),
+            None => Ok(None),
+        }
+    }
+    // the next game's record, to be decoded later, None at the end of the stream
+    pub fn read_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut length = 0;
+        for shift in (0..usize::BITS).step_by(7) {
+            let mut byte = [0];
+            if self.inner.read(&mut byte)? == 0 {
+                return match shift {
+                    0 => Ok(None),
+                    _ => Err(ErrorKind::UnexpectedEof.into()),
+                };
+            }
+            length |= (byte[0] as usize & 0x7f) << shift;
+            if byte[0] & 0x80 == 0 {
+                let mut record = Vec::new();
+                self.inner
+                    .by_ref()
+                    .take(length as u64)
+                    .read_to_end(&mut record)?;
+                if record.len() < length {
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
+                return Ok(Some(record));
+            }
+        }
+        Err(invalid("record too long"))
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = io::Result<Game>;
+    fn next(&mut self) -> Option<io::Result<Game>> {
+        self.read_game().transpose()
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+// one game's record, without its length
+pub fn encode(game: &Game) -> io::Result<Vec<u8>> {
+    let variant = game.state.variant;
+    let mut bits = Bits::default();
+    bits.string(variant.name());
+    let result = pgn::result(game);
+    let result = RESULTS.iter().position(|known| *known == result);
+    bits.number(result.unwrap_or(0), 8);
+    let tree = game.tree();
+    // an ending the moves don't show, e.g. a resignation, is only known from this
+    match game.finished() {
+        Some(finished) => {
+            let outcome = tree.node(finished).unwrap().state.outcome.as_ref();
+            bits.bit(true);
+            encode_outcome(outcome.expect("finished games have an outcome"), &mut bits)?;
+            bits.varint(tree.preorder().position(|id| id == finished).unwrap());
+        }
+        None => bits.bit(false),
+    }
+    bits.varint(game.tags.len());
+    for (name, value) in &game.tags {
+        bits.string(name);
+        bits.string(value);
+    }
+    let annotated = tree
+        .preorder()
+        .any(|id| !tree.node(id).unwrap().annotation.is_empty());
+    bits.bit(annotated);
+    encode_node(game, MoveTree::ROOT, annotated, &mut bits)?;
+    let current = tree.preorder().position(|id| id == tree.current());
+    bits.varint(current.unwrap_or(0));
+    Ok(bits.bytes)
+}
+
+// the winner, 0 for a draw, and the reason's index in REASONS
+fn encode_outcome(outcome: &Outcome, bits: &mut Bits) -> io::Result<()> {
+    let winner = match outcome {
+        Outcome::Win(player_id, _) => player_id.0,
+        Outcome::Draw(_) => 0,
+    };
+    let reason = REASONS
+        .iter()
+        .position(|reason| *reason == outcome.reason());
+    bits.varint(winner);
+    bits.index(
+        reason.ok_or_else(|| invalid("unknown outcome"))?,
+        REASONS.len(),
+    );
+    Ok(())
+}
+
+fn encode_node(game: &Game, id: NodeId, annotated: bool, bits: &mut Bits) -> io::Result<()> {
+    let node = game.tree().node(id).unwrap();
+    if annotated {
+        bits.bit(!node.annotation.is_empty());
+        if !node.annotation.is_empty() {
+            encode_annotation(&node.annotation, bits);
+        }
+    }
+    for child in &node.children {
+        let child_node = game.tree().node(*child).unwrap();
+        let play = child_node
+            .play
+            .as_ref()
+            .ok_or_else(|| invalid("move wasn't recorded"))?;
+        let plays = game.move_list(&node.state);
+        let index = plays.iter().position(|legal| legal == play);
+        bits.bit(true);
+        bits.index(index.ok_or_else(|| invalid("illegal move"))?, plays.len());
+        if node.state.variant.has_duck() {
+            let squares = duck_squares(&game.after(&node.state, play));
+            let duck = child_node.duck.as_ref();
+            let index = squares.iter().position(|square| Some(square) == duck);
+            bits.index(
+                index.ok_or_else(|| invalid("duck wasn't placed"))?,
+                squares.len(),
+            );
+        }
+        encode_node(game, *child, annotated, bits)?;
+    }
+    bits.bit(false);
+    Ok(())
+}
+
+fn encode_annotation(annotation: &Annotation, bits: &mut Bits) {
+    bits.string(&annotation.comment);
+    bits.varint(annotation.nags.len());
+    for nag in &annotation.nags {
+        bits.number(*nag as usize, 8);
+    }
+    bits.bit(annotation.clock.is_some());
+    if let Some(clock) = annotation.clock {
+        bits.varint(clock.as_millis() as usize);
+    }
+    bits.varint(annotation.arrows.len());
+    for arrow in &annotation.arrows {
+        bits.number(arrow.colour as usize, 8);
+        bits.square(&arrow.from);
+        bits.square(&arrow.to);
+    }
+    bits.varint(annotation.squares.len());
+    for highlight in &annotation.squares {
+        bits.number(highlight.colour as usize, 8);
+        bits.square(&highlight.square);
+    }
+}
+
+// inverse of encode
+pub fn decode(record: &[u8]) -> io::Result<Game> {
+    let mut bits = Unbits {
+        bytes: record,
+        position: 0,
+    };
+    let variant = Variant::from_name(&bits.string()?).ok_or_else(|| invalid("unknown variant"))?;
+    let result = RESULTS.get(bits.number(8)?).copied();
+    let finished = match bits.bit()? {
+        true => Some((decode_outcome(&mut bits)?, bits.varint()?)),
+        false => None,
+    };
+    let mut tags = Tags::new();
+    for _ in 0..bits.varint()? {
+        tags.push((bits.string()?, bits.string()?));
+    }
+    let mut game = pgn::start(variant, tags).map_err(invalid)?;
+    let annotated = bits.bit()?;
+    decode_node(&mut game, MoveTree::ROOT, annotated, &mut bits)?;
+    let current = bits.varint()?;
+    let id = game.tree().preorder().nth(current);
+    let id = id.ok_or_else(|| invalid("no such position"))?;
+    if let Some((outcome, finished)) = finished {
+        let finished = game.tree().preorder().nth(finished);
+        game.goto_node(finished.ok_or_else(|| invalid("no such position"))?);
+        game.finish(outcome);
+    }
+    game.goto_node(id);
+    if let Some(result) = result.filter(|result| *result != pgn::result(&game)) {
+        pgn::set_tag(&mut game.tags, "Result", result);
+    }
+    Ok(game)
+}
+
+// inverse of encode_outcome
+fn decode_outcome(bits: &mut Unbits) -> io::Result<Outcome> {
+    let winner = bits.varint()?;
+    let reason = REASONS[bits.index(REASONS.len())?];
+    Ok(match winner {
+        0 => Outcome::Draw(reason),
+        winner => Outcome::Win(PlayerId(winner), reason),
+    })
+}
+
+fn decode_node(game: &mut Game, id: NodeId, annotated: bool, bits: &mut Unbits) -> io::Result<()> {
+    if annotated && bits.bit()? {
+        let annotation = decode_annotation(bits)?;
+        game.goto_node(id);
+        *game.annotate() = annotation;
+    }
+    while bits.bit()? {
+        game.goto_node(id);
+        let plays = game.move_list(&game.state);
+        let play = &plays[bits.index(plays.len())?];
+        if !game.play(play) {
+            return Err(invalid("illegal move"));
+        }
+        if game.state.variant.has_duck() {
+            let squares = duck_squares(&game.state);
+            let duck = &squares[bits.index(squares.len())?];
+            game.place_duck(duck);
+        }
+        let child = game.tree().current();
+        decode_node(game, child, annotated, bits)?;
+    }
+    Ok(())
+}
+
+fn decode_annotation(bits: &mut Unbits) -> io::Result<Annotation> {
+    let mut annotation = Annotation {
+        comment: bits.string()?,
+        ..Annotation::default()
+    };
+    for _ in 0..bits.varint()? {
+        annotation.nags.push(bits.number(8)? as u8);
+    }
+    if bits.bit()? {
+        annotation.clock = Some(Duration::from_millis(bits.varint()? as u64));
+    }
+    for _ in 0..bits.varint()? {
+        annotation.arrows.push(Arrow {
+            colour: bits.number(8)? as u8 as char,
+            from: bits.square()?,
+            to: bits.square()?,
+        });
+    }
+    for _ in 0..bits.varint()? {
+        annotation.squares.push(Highlight {
+            colour: bits.number(8)? as u8 as char,
+            square: bits.square()?,
+        });
+    }
+    Ok(annotation)
+}
+
+// the squares the duck could be placed on once a move has been made, in order
+fn duck_squares(state: &GameState) -> Vec<Vector> {
+    let (rows, cols) = state.board.shape();
+    let squares =
+        (0..rows as i32).flat_map(|row| (0..cols as i32).map(move |col| Vector(col, row)));
+    squares.filter(|pos| state.board[pos].is_none()).collect()
+}
+
+// bits needed to tell apart 'choices' things, none when there is only one
+fn width(choices: usize) -> u32 {
+    usize::BITS - choices.saturating_sub(1).leading_zeros()
+}
+
+// bytes being written a bit at a time, most significant bit first
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    used: u32, // bits used in the last byte, 0 if it's full
+}
+
+impl Bits {
+    fn bit(&mut self, bit: bool) {
+        if self.used == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0x80 >> self.used;
+        }
+        self.used = (self.used + 1) % 8;
+    }
+    fn number(&mut self, number: usize, width: u32) {
+        for bit in (0..width).rev() {
+            self.bit(number >> bit & 1 == 1);
+        }
+    }
+    // one of 'choices' things, see width
+    fn index(&mut self, index: usize, choices: usize) {
+        self.number(index, width(choices));
+    }
+    // 7 bits at a time, with the top bit set on all but the last group
+    fn varint(&mut self, mut number: usize) {
+        loop {
+            let more = number >= 0x80;
+            self.number(number & 0x7f | (more as usize) << 7, 8);
+            number >>= 7;
+            if !more {
+                break;
+            }
+        }
+    }
+    fn string(&mut self, string: &str) {
+        self.varint(string.len());
+        for byte in string.bytes() {
+            self.number(byte as usize, 8);
+        }
+    }
+    fn square(&mut self, square: &Vector) {
+        self.varint(square.0 as usize);
+        self.varint(square.1 as usize);
+    }
+}
+
+// inverse of Bits
+struct Unbits<'a> {
+    bytes: &'a [u8],
+    position: usize, // in bits
+}
+
+impl Unbits<'_> {
+    fn bit(&mut self) -> io::Result<bool> {
+        let byte = self
+            .bytes
+            .get(self.position / 8)
+            .ok_or(ErrorKind::UnexpectedEof)?;
+        let bit = byte & 0x80 >> (self.position % 8) != 0;
+        self.position += 1;
+        Ok(bit)
+    }
+    fn number(&mut self, width: u32) -> io::Result<usize> {
+        let mut number = 0;
+        for _ in 0..width {
+            number = number << 1 | self.bit()? as usize;
+        }
+        Ok(number)
+    }
+    fn index(&mut self, choices: usize) -> io::Result<usize> {
+        match self.number(width(choices))? {
+            index if index < choices => Ok(index),
+            _ => Err(invalid("no such move")),
+        }
+    }
+    fn varint(&mut self) -> io::Result<usize> {
+        let mut number = 0;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.number(8)?;
+            number |= (byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(number);
+            }
+        }
+        Err(invalid("number too large"))
+    }
+    fn string(&mut self) -> io::Result<String> {
+        let mut bytes = Vec::new();
+        for _ in 0..self.varint()? {
+            bytes.push(self.number(8)? as u8);
+        }
+        String::from_utf8(bytes).map_err(|_| invalid("text isn't UTF-8"))
+    }
+    fn square(&mut self) -> io::Result<Vector> {
+        Ok(Vector(self.varint()? as i32, self.varint()? as i32))
+    }
+}
@@ -1,32 +1,150 @@
+use crate::annotation::Annotation;
 use crate::board::{Board, Matrix, Vector};
-use crate::piece::Piece;
+use crate::cube;
+use crate::input::{parse, Input};
+use crate::outcome::{GameResult, Outcome, WinCondition};
+use crate::pgn;
+use crate::piece::{Piece, PieceKind, PlayerId};
+use crate::ruleset::{self, Ruleset};
+use crate::san;
+use crate::setup::Setup;
+use crate::tree::{MoveTree, NodeId};
+use crate::variant::Variant;
 
+/*
+ * with the serde feature a game can be saved, see Game::save, every field but the rules and the
+ * selection is kept, a loaded game is played by the built-in rules from the tree's first position
+ */
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "SavedGame"))]
 pub struct Game {
     pub state: GameState,
-    // specifically modified by rewinds
-    history: Vec<GameState>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rules: Box<dyn Ruleset>, // how the game is played, see Ruleset
+    // every line played so far, specifically modified by rewinds, see MoveTree
+    tree: MoveTree,
+    pending: Option<Pending>, // the move being made, recorded in the tree once the turn ends
+    redo: Vec<NodeId>,        // moves taken back with undo, the most recent last
+    finished: Option<NodeId>, // the position the game was ended at by finish, e.g. a resignation
+    pub tags: pgn::Tags,      // PGN tags such as White or Event
+    draw_offer: Option<PlayerId>, // the player offering a draw, until it's answered
     // temporary state, possibly not persistent
+    #[cfg_attr(feature = "serde", serde(skip))]
     selection: Option<Vector>, // position of Piece selected to be moved
-    valid: Matrix<bool>,       // boolean matrix of legal moves
+    #[cfg_attr(feature = "serde", serde(skip))]
+    valid: Matrix<bool>, // boolean matrix of legal moves
 }
 
+// the fields of Game which are saved, see Game::save
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SavedGame {
+    state: GameState,
+    tree: MoveTree,
+    pending: Option<Pending>,
+    redo: Vec<NodeId>,
+    #[serde(default)]
+    finished: Option<NodeId>,
+    tags: pgn::Tags,
+    #[serde(default)]
+    draw_offer: Option<PlayerId>,
+}
+
+#[cfg(feature = "serde")]
+impl From<SavedGame> for Game {
+    fn from(saved: SavedGame) -> Game {
+        let start = saved.tree.node(MoveTree::ROOT).unwrap().state.clone();
+        let (rows, cols) = saved.state.board.shape();
+        Game {
+            state: saved.state,
+            rules: ruleset::built_in(start),
+            tree: saved.tree,
+            pending: saved.pending,
+            redo: saved.redo,
+            finished: saved.finished,
+            tags: saved.tags,
+            draw_offer: saved.draw_offer,
+            selection: None,
+            valid: Matrix::new(false, rows, cols),
+        }
+    }
+}
+
+// a move being made, kept until the turn ends, in duck chess once the duck has been placed
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Pending {
+    san: String,
+    play: Play,
+    duck: Option<Vector>,
+}
+
+/*
+ * a move as the player to move chooses it, from the squares they select and the piece they promote
+ * to, see Game::move_list
+ */
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Play {
+    Move(Vector, Vector, Option<PieceKind>), // from, to, and what the piece promotes to if the move promotes, see Variant::promotion_pieces
+    Drop(PieceKind, Vector),
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GameState {
     pub board: Board, // pieces on the board
-    turn: usize,      // next player to move
+    turn: PlayerId,   // next player to move
     halfmove_counter: u32,
     players: Vec<Player>, // off by one, players[0] corresponds to player 1 (piece.owner 1)
+    pub variant: Variant,
+    pub captured: Vec<Piece>, // every piece taken off the board so far, in order
+    pub outcome: Option<Outcome>, // set once one of the variant's win conditions is met
+    pub placing_duck: bool,   // in duck chess, the player to move has moved and must place the duck
+    pub positions: Vec<Position>, // every position reached so far, for spotting repetitions
+    pub castling: Vec<PieceKind>, // what a K may castle with
+    pub count: Option<Count>, // in makruk, the moves left before the game is drawn
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Player {
     pub direction: Vector,
     pub recent_move: Option<Move>, // each player tracks most recent move for en passant
-    state: State,
-    pub king_pos: Vector,
+    pub state: State,
+    pub checks: u32, // number of times this player's K has been put in check
+    pub king_pos: Option<Vector>, // None if the player has no royal K
+    pub promotion: Option<PieceKind>, // what this player's P promote to, see Variant::promotion_pieces
+    pub hand: Vec<PieceKind>,         // captured pieces which can be dropped back onto the board
+    pub royal: Option<PieceKind>,     // what king_pos holds, K unless a setup says otherwise
+    pub promotion_ranks: Vec<i32>, // ranks (files, for players moving sideways) where this player's P promote
+}
+
+// a snapshot of the board, the hands, and who is to move
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub key: String,
+    pub turn: PlayerId,
+    pub check: bool, // whether the player to move was in check
+}
+
+/*
+ * makruk's counting rules, the game is drawn once 'moves' more moves have been made since the
+ * halfmove 'start' without a checkmate
+ * the count starts at 64 moves once no P are left (board's honour), and starts again once one
+ * player is down to a bare K (pieces' honour), with fewer moves the stronger the other side is
+ */
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Count {
+    pub start: u32,
+    pub moves: u32,
+    pub bare_king: bool, // whether this is the pieces' honour count
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum State {
     Check,     // K is threatened
     Stalemate, // K has no legal moves
@@ -34,12 +152,133 @@ pub enum State {
     None,      // None of the above apply
 }
 
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveKind {
+    Standard, // piece moves from 'start' to 'end'
+    Drop,     // piece is placed from hand onto 'end', 'start' == 'end'
+}
+
 // stores a move of some 'piece', from position 'start' to position 'end'
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Move {
     pub piece: Piece,
     pub start: Vector,
     pub end: Vector,
+    pub kind: MoveKind,
+}
+
+// the K of 'owner', the first found
+fn find_king(board: &Board, owner: PlayerId) -> Result<Vector, &'static str> {
+    let (rows, cols) = board.shape();
+    let is_king = |piece: &Piece| piece.kind == PieceKind::King && piece.owner == owner;
+    for row in 0..rows as i32 {
+        for col in 0..cols as i32 {
+            if board[&Vector(col, row)].as_ref().is_some_and(is_king) {
+                return Ok(Vector(col, row));
+            }
+        }
+    }
+    Err("each player needs a K")
+}
+
+// marks each P off its owner's starting ranks as moved, directions[0] is player 1's
+pub fn pawns_moved(board: &mut Board, directions: &[Vector], variant: Variant) {
+    let (rows, cols) = board.shape();
+    for (row, pieces) in board.0 .0.iter_mut().enumerate() {
+        for (col, piece) in pieces.iter_mut().enumerate() {
+            let piece = match piece {
+                Some(piece) if piece.kind == PieceKind::Pawn => piece,
+                _ => continue,
+            };
+            let rank = match piece.owner.index().and_then(|index| directions.get(index)) {
+                Some(Vector(0, 1)) => row,
+                Some(Vector(0, _)) => rows - 1 - row,
+                Some(Vector(1, _)) => col,
+                Some(_) => cols - 1 - col,
+                None => continue,
+            };
+            piece.has_moved = !variant.pawn_ranks().contains(&rank);
+        }
+    }
+}
+
+/*
+ * marks every K and piece it castles with as moved, except those FEN castling rights keep free,
+ * given as KQkq (outermost piece on that side) or as the file of the piece (e.g. Jj), - for none
+ */
+pub fn castling_rights(
+    board: &mut Board,
+    rights: &str,
+    castling: &[PieceKind],
+) -> Result<(), &'static str> {
+    let (_, cols) = board.shape();
+    for piece in board.0 .0.iter_mut().flatten().flatten() {
+        if piece.kind == PieceKind::King || castling.contains(&piece.kind) {
+            piece.has_moved = true;
+        }
+    }
+    if rights == "-" {
+        return Ok(());
+    }
+    for right in rights.chars() {
+        let owner = PlayerId(if right.is_ascii_uppercase() { 1 } else { 2 });
+        let king_pos = find_king(board, owner)?;
+        let rank = king_pos.1;
+        let is_rook = |board: &Board, col: &i32| {
+            let piece = board[&Vector(*col, rank)].as_ref();
+            piece.is_some_and(|piece| castling.contains(&piece.kind) && piece.owner == owner)
+        };
+        let rook_file = match right.to_ascii_uppercase() {
+            'K' => (king_pos.0 + 1..cols as i32)
+                .rev()
+                .find(|col| is_rook(board, col)),
+            'Q' => (0..king_pos.0).find(|col| is_rook(board, col)),
+            file @ 'A'..='Z' => Some(file as i32 - 'A' as i32)
+                .filter(|col| *col < cols as i32 && is_rook(board, col)),
+            _ => return Err("unexpected character in castling rights"),
+        };
+        let rook_file = rook_file.ok_or("castling right without a matching R")?;
+        for square in [king_pos, Vector(rook_file, rank)] {
+            if let Some(piece) = &mut board[&square] {
+                piece.has_moved = false;
+            }
+        }
+    }
+    Ok(())
+}
+
+// the P double move by 'mover', whose P move in 'direction', which passed the en passant square 'passed'
+pub fn double_move(
+    board: &Board,
+    passed: &Vector,
+    direction: &Vector,
+    mover: PlayerId,
+) -> Result<Move, &'static str> {
+    let end = passed.clone() + direction.clone();
+    let is_pawn = |piece: &Piece| piece.kind == PieceKind::Pawn && piece.owner == mover;
+    if !board.in_bounds(&end) || !board[&end].as_ref().is_some_and(is_pawn) {
+        return Err("en passant square is not behind a P");
+    }
+    Ok(Move {
+        piece: board[&end].clone().expect("checked to be a P"),
+        start: passed.clone() + direction.clone() * -1,
+        end,
+        kind: MoveKind::Standard,
+    })
+}
+
+// the last 'count' ranks (or files) a player moving in 'direction' reaches, counted from 0
+pub fn far_ranks(direction: &Vector, (rows, cols): (usize, usize), count: i32) -> Vec<i32> {
+    let (forwards, len) = match direction {
+        Vector(0, forwards) => (*forwards, rows as i32),
+        Vector(forwards, _) => (*forwards, cols as i32),
+    };
+    match forwards > 0 {
+        true => (len - count..len).collect(),
+        false => (0..count).collect(),
+    }
 }
 
 impl Move {
@@ -49,48 +288,67 @@ impl Move {
         displacement.0 * displacement.0 + displacement.1 * displacement.1
     }
     pub fn is_diag(&self) -> bool {
-        match self.end.clone() + self.start.clone() * -1 {
-            Vector(1 | -1, 1 | -1) => true,
-            _ => false,
-        }
+        matches!(
+            self.end.clone() + self.start.clone() * -1,
+            Vector(1 | -1, 1 | -1)
+        )
     }
 }
 
 impl Game {
-    // create standard chess game
-    pub fn new() -> Game {
-        Game {
-            state: GameState {
-                board: Board::standard(),
-                turn: 1,
-                halfmove_counter: 1,
-                players: vec![
-                    Player {
-                        direction: Vector(0, 1),
-                        recent_move: None,
-                        state: State::None,
-                        king_pos: Vector(4, 0),
-                    },
-                    Player {
-                        direction: Vector(0, -1),
-                        recent_move: None,
-                        state: State::None,
-                        king_pos: Vector(4, 7),
-                    },
-                ],
-            },
-            history: Vec::new(),
+    // create a game from a setup, Setup::default() is standard chess
+    pub fn new(setup: &Setup) -> Game {
+        Self::with_rules(ruleset::from_setup(setup))
+    }
+    pub fn from_variant(variant: Variant) -> Game {
+        Self::with_rules(ruleset::from_variant(variant))
+    }
+    // played by the built-in rules of the position's variant, see ruleset::built_in
+    pub fn from_state(state: GameState) -> Game {
+        Self::with_rules(ruleset::built_in(state))
+    }
+    // create a game played by any rules, starting from their starting position
+    pub fn with_rules(rules: Box<dyn Ruleset>) -> Game {
+        let state = rules.start();
+        let (rows, cols) = state.board.shape();
+        let mut game = Game {
+            tree: MoveTree::new(state.clone()),
+            pending: None,
+            redo: Vec::new(),
+            finished: None,
+            tags: Vec::new(),
+            draw_offer: None,
+            state,
+            rules,
             selection: None,
-            valid: Matrix(vec![vec![false; 8]; 8]),
-        }
+            valid: Matrix::new(false, rows, cols),
+        };
+        game.begin();
+        game
+    }
+    // works out where each player stands in the starting position, by the game's rules
+    fn begin(&mut self) {
+        self.state.positions.clear();
+        self.state.count = None;
+        self.update_states();
+        self.rules.record_position(&mut self.state);
+        self.rules.update_count(&mut self.state);
+        self.state.outcome = self.rules.outcome(&self.state);
+        self.tree = MoveTree::new(self.state.clone());
     }
     pub fn current_player(&self) -> &Player {
         self.state.get_player(self.state.turn)
     }
     pub fn show_moves(&mut self, pos: Vector) {
-        let selected = &self.state.board[&pos];
-        let (valid, threat) = selected.claim_squares()(&pos, &self.state); // ask what squares it wants
-        (self.valid, _) = selected.speculate(valid, threat, &pos, &self.state);
+        self.valid = self.rules.legal_moves(&self.state, &pos);
+    }
+    // chooses what the current player's P promote to, false if the variant doesn't allow it
+    pub fn set_promotion(&mut self, promotion: Option<PieceKind>) -> bool {
+        if !self.state.variant.promotion_pieces().contains(&promotion) {
+            return false;
+        }
+        self.state.get_player_mut(self.state.turn).promotion = promotion;
+        true
     }
     fn deselect(&mut self) {
         self.selection = None;
@@ -102,11 +360,16 @@ impl Game {
     }
     // if no selection readies piece to be moved updating selection, and calculating validity in validity matrix
     // if has selection, moves piece if allowed, otherwise deselects
+    // in duck chess, the selection after a move is where the duck goes
     pub fn select(&mut self, pos: Vector) {
-        if self.selection.is_none() {
+        if self.state.outcome.is_some() {
+            self.deselect();
+        } else if self.state.placing_duck {
+            self.place_duck(&pos);
+        } else if self.selection.is_none() {
             self.deselect();
             let target = &self.state.board[&pos]; //TODO: panics if out of bounds
-            if target.id != ' ' && target.owner == self.state.turn {
+            if matches!(target, Some(piece) if piece.owner == self.state.turn) {
                 self.selection = Some(pos.clone());
                 self.show_moves(pos);
             }
@@ -117,7 +380,8 @@ impl Game {
     // moves piece at 'from' to position at 'to' erases piece at 'to' if occupied
     pub fn move_piece(&mut self, pos: Vector) {
         debug_assert!(self.selection.is_some());
-        let is_turn = self.state.turn == self.state.board[&self.selection.clone().unwrap()].owner;
+        let selected = self.state.get_piece(&self.selection.clone().unwrap());
+        let is_turn = matches!(selected, Some(piece) if piece.owner == self.state.turn);
         let is_legal = self.valid[&pos];
         if is_turn && is_legal {
             self.move_piece_unchecked(&self.selection.clone().unwrap(), &pos);
@@ -127,101 +391,1226 @@ impl Game {
     // moves piece at 'from' to position at 'to' erases piece at 'to' if occupied
     // doesn't check legalitly
     pub fn move_piece_unchecked(&mut self, from: &Vector, to: &Vector) {
-        assert_ne!(self.state.board[from].id, ' ');
-        // record current state in history
-        self.history.push(self.state.clone());
-        self.state.players[self.state.turn as usize - 1].recent_move = Some(Move {
-            piece: self.state.board[from].clone(),
-            start: from.clone(),
-            end: to.clone(),
+        assert!(self.state.board[from].is_some());
+        let moved = self.state.board[from].clone().unwrap();
+        let body = san::move_body(&self.state, self.rules.as_ref(), from, to);
+        let choice = match moved.owner.index() {
+            Some(_) if self.promotes(&self.state, from, to) => {
+                self.state.get_player(moved.owner).promotion
+            }
+            _ => None,
+        };
+        self.rules.apply_move(&mut self.state, from, to);
+        let promotion = san::promotion(&moved, self.state.get_piece(to));
+        self.pending = Some(Pending {
+            san: body + &promotion,
+            play: Play::Move(from.clone(), to.clone(), choice),
+            duck: None,
         });
-        // perform move side effects (e.g. K castle and P en passant)
-        self.state.board[from].side_effects()(from, &mut self.state);
-        // modify board by swapping pieces around
-        self.state.board[to] = self.state.board[from].clone();
-        self.state.board[from] = Piece::new();
-        self.state.board[to].has_moved = true;
-        // update king_pos
-        if self.state.board[to].id == 'K' {
-            self.state.players[self.state.turn - 1].king_pos = to.clone();
+        // in duck chess the turn only ends once the duck has been placed
+        if self.state.variant.has_duck() {
+            self.state.placing_duck = true;
+        } else {
+            self.end_turn();
+        }
+    }
+    // moves the duck to an empty square, ending the turn, returns whether the duck was placed
+    pub fn place_duck(&mut self, pos: &Vector) -> bool {
+        if !self.state.placing_duck || !self.state.in_bounds(pos) || self.state.board[pos].is_some()
+        {
+            return false;
+        }
+        for square in self.state.board.0 .0.iter_mut().flatten() {
+            if square.as_ref().is_some_and(Piece::is_duck) {
+                *square = None;
+            }
+        }
+        self.state.board[pos] = Some(Piece::duck());
+        self.state.placing_duck = false;
+        // the duck's square is written after the move, e.g. e4,e5
+        if let Some(pending) = &mut self.pending {
+            pending.san.push(',');
+            pending
+                .san
+                .push_str(&self.state.variant.notation(pos).unwrap_or_default());
+            pending.duck = Some(pos.clone());
+        }
+        self.end_turn();
+        true
+    }
+    // places a piece from the current player's hand onto an empty square, if legal
+    // returns whether the drop was made
+    pub fn drop_piece(&mut self, kind: PieceKind, pos: &Vector) -> bool {
+        let turn = self.state.turn;
+        if self.state.outcome.is_some() {
+            return false;
+        }
+        if !self.state.in_bounds(pos) || !self.rules.drop_squares(&self.state, kind, turn)[pos] {
+            return false;
+        }
+        self.deselect();
+        self.pending = Some(Pending {
+            san: san::drop(&self.state, kind, pos),
+            play: Play::Drop(kind, pos.clone()),
+            duck: None,
+        });
+        let piece = self.state.dropped_piece(kind, turn, pos);
+        let player = self.state.get_player_mut(turn);
+        let index = player.hand.iter().position(|held| *held == kind).unwrap();
+        player.hand.remove(index);
+        // a K placed from hand, as in sittuyin, has to be kept safe from then on
+        if Some(kind) == player.royal {
+            player.king_pos = Some(pos.clone());
+        }
+        player.recent_move = Some(Move {
+            piece: piece.clone(),
+            start: pos.clone(),
+            end: pos.clone(),
+            kind: MoveKind::Drop,
+        });
+        self.state.board[pos] = Some(piece);
+        self.end_turn();
+        true
+    }
+    // gives player_id a piece to drop from outside the game, e.g. from a bughouse partner
+    pub fn add_to_hand(&mut self, player_id: PlayerId, kind: PieceKind) {
+        self.state.add_to_hand(player_id, kind);
+        // a new piece in hand may be able to block a checkmate
+        self.update_states();
+    }
+    fn end_turn(&mut self) {
+        // moving rather than answering a draw offer declines it
+        if self
+            .draw_offer
+            .is_some_and(|offer| offer != self.state.turn)
+        {
+            self.draw_offer = None;
         }
         // update turn counters
-        self.state.turn = self.state.turn % self.state.players.len() + 1;
+        self.state.turn = self.state.turn.next(self.state.players.len());
         self.state.halfmove_counter += 1;
+        self.update_states();
+        let player = self.state.get_player_mut(self.state.turn);
+        if matches!(player.state, State::Check | State::Checkmate) {
+            player.checks += 1;
+        }
+        self.rules.record_position(&mut self.state);
+        self.rules.update_count(&mut self.state);
+        self.state.outcome = self.rules.outcome(&self.state);
+        if let Some(pending) = self.pending.take() {
+            let san = pending.san + san::check(&self.state);
+            let id = self
+                .tree
+                .play(san, pending.play, pending.duck, self.state.clone());
+            // replaying the move just undone keeps the rest of the undone moves to redo
+            if self.redo.last() == Some(&id) {
+                self.redo.pop();
+            } else {
+                self.redo.clear();
+            }
+        }
+    }
+    fn update_states(&mut self) {
         // update the end state of each player, enum State
-        for player_id in 1..=self.state.players.len() {
-            self.state.players[player_id - 1].state = self.state.update_check(player_id);
+        for player_id in PlayerId::all(self.state.players.len()) {
+            self.state.get_player_mut(player_id).state =
+                self.rules.update_check(&self.state, player_id);
         }
     }
 
+    /*
+     * goes back 'halfmoves' moves, always at least one
+     * the moves taken back stay in the tree, so playing something else starts a variation
+     */
     pub fn rewind(&mut self, halfmoves: u32) {
-        let target_time = self.history.len() - halfmoves as usize;
-        self.history.truncate(target_time + 1);
-        self.state = self.history.pop().unwrap();
+        for _ in 0..halfmoves.max(1) {
+            self.undo();
+        }
+    }
+    /*
+     * takes back the last move, which redo plays again, false at the start of the game
+     * in duck chess a move whose duck hasn't been placed yet is taken back without being kept
+     */
+    pub fn undo(&mut self) -> bool {
+        if self.state.placing_duck {
+            self.sync();
+            return true;
+        }
+        let current = self.tree.current();
+        if !self.tree.back() {
+            return false;
+        }
+        self.redo.push(current);
+        self.sync();
+        true
+    }
+    // plays the last move taken back by undo again, false if there is none
+    pub fn redo(&mut self) -> bool {
+        let moved = match self.redo.pop() {
+            Some(id) => self.tree.goto(id),
+            None => false,
+        };
+        self.sync();
+        moved
+    }
+    /*
+     * jumps to the position after 'ply' halfmoves of the line being played, by undoing moves or
+     * redoing them, false if the line isn't that long
+     */
+    pub fn goto(&mut self, ply: usize) -> bool {
+        while self.tree.ply(self.tree.current()) > ply {
+            self.undo();
+        }
+        while self.tree.ply(self.tree.current()) < ply {
+            if !self.redo() {
+                return false;
+            }
+        }
+        self.sync();
+        true
+    }
+    // steps back one move, false at the start of the game
+    pub fn back(&mut self) -> bool {
+        let moved = self.tree.back();
+        self.redo.clear();
+        self.sync();
+        moved
+    }
+    // steps forward one move along the main line, false at the end of a line
+    pub fn forward(&mut self) -> bool {
+        let moved = self.tree.forward();
+        self.redo.clear();
+        self.sync();
+        moved
+    }
+    // jumps to the position after any move in the tree, false if there is no such move
+    pub fn goto_node(&mut self, id: NodeId) -> bool {
+        let moved = self.tree.goto(id);
+        self.redo.clear();
+        self.sync();
+        moved
+    }
+    // makes the line leading to the current position the main line
+    pub fn promote_variation(&mut self) -> bool {
+        self.tree.promote(self.tree.current())
+    }
+    // removes the move leading to the current position and every move after it, going back a move
+    pub fn delete_variation(&mut self) -> bool {
+        let deleted = self.tree.delete(self.tree.current());
+        if self.finished.is_some_and(|id| self.tree.node(id).is_none()) {
+            self.finished = None;
+        }
+        self.redo.clear();
+        self.sync();
+        deleted
+    }
+    pub fn tree(&self) -> &MoveTree {
+        &self.tree
+    }
+    // sets the position to the tree's current node, dropping any half made move
+    // once the game has been ended it stays over, wherever in its moves it is looked at
+    fn sync(&mut self) {
+        self.state = self.tree.state().clone();
+        if let Some(outcome) = self.outcome() {
+            self.state.outcome = Some(outcome.clone());
+        }
+        self.pending = None;
+        self.draw_offer = None;
         self.deselect();
     }
+    // how the game stands, see outcome for how it ended
+    pub fn result(&self) -> GameResult {
+        GameResult::of(self.state.outcome.as_ref())
+    }
+    /*
+     * 'player_id' gives up, the player after them wins, false if the game is already over
+     * a half made move, such as one waiting for its duck, is taken back first
+     */
+    pub fn resign(&mut self, player_id: PlayerId) -> bool {
+        let winner = player_id.next(self.state.num_players());
+        self.finish(Outcome::Win(winner, "resignation"))
+    }
+    /*
+     * 'player_id' offers a draw, which stands until another player answers it or moves
+     * false if the game is already over
+     */
+    pub fn offer_draw(&mut self, player_id: PlayerId) -> bool {
+        if self.state.outcome.is_some() {
+            return false;
+        }
+        self.draw_offer = Some(player_id);
+        true
+    }
+    // the player offering a draw, if there is an offer
+    pub fn draw_offer(&self) -> Option<PlayerId> {
+        self.draw_offer
+    }
+    // 'player_id' accepts another player's draw offer, ending the game, false if there is none
+    pub fn accept_draw(&mut self, player_id: PlayerId) -> bool {
+        match self.draw_offer {
+            Some(offer) if offer != player_id => self.finish(Outcome::Draw("agreement")),
+            _ => false,
+        }
+    }
+    // 'player_id' turns down another player's draw offer, false if there is none
+    pub fn decline_draw(&mut self, player_id: PlayerId) -> bool {
+        match self.draw_offer {
+            Some(offer) if offer != player_id => {
+                self.draw_offer = None;
+                true
+            }
+            _ => false,
+        }
+    }
+    /*
+     * ends the game at the current position with 'outcome', false if it's already over
+     * the outcome is kept on the position in the tree, so it's written with the game
+     * moves after it, e.g. ones taken back before resigning, stay in the tree to be looked at
+     */
+    pub fn finish(&mut self, outcome: Outcome) -> bool {
+        if self.state.outcome.is_some() {
+            return false;
+        }
+        self.sync();
+        let current = self.tree.current();
+        if let Some(state) = self.tree.state_mut(current) {
+            state.outcome = Some(outcome.clone());
+        }
+        self.finished = Some(current);
+        self.state.outcome = Some(outcome);
+        true
+    }
+    // the position the game was ended at by finish, None if it wasn't
+    pub fn finished(&self) -> Option<NodeId> {
+        self.finished
+    }
+    // how the game was ended by finish, None if it wasn't
+    fn outcome(&self) -> Option<&Outcome> {
+        let node = self.tree.node(self.finished?)?;
+        node.state.outcome.as_ref()
+    }
+    // the comments, NAGs and so on about the move just made, or the game if no move has been
+    pub fn annotate(&mut self) -> &mut Annotation {
+        let current = self.tree.current();
+        self.tree
+            .annotation_mut(current)
+            .expect("current node exists")
+    }
+    // the game as PGN, with every variation and annotation, see pgn::write
+    pub fn pgn(&self) -> String {
+        pgn::write(self)
+    }
+    // writes the game, with every variation, to 'path' as JSON, to be read back with load
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+    // reads a game written by save, which carries on from where it was saved
+    #[cfg(feature = "serde")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Game> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+    // reads a game from PGN, see pgn::read
+    pub fn from_pgn(text: &str) -> Result<Game, &'static str> {
+        pgn::read(text)
+    }
+    /*
+     * plays a move written in SAN, e.g. Nbd7, exd5, e8=Q+, O-O, P@e4 or, in duck chess, e4,e5
+     * the move is found by writing each legal move in SAN, so it reads whatever san writes
+     */
+    pub fn play_san(&mut self, san: &str) -> Result<(), &'static str> {
+        if self.state.outcome.is_some() {
+            return Err("the game is over");
+        }
+        let variant = self.state.variant;
+        // check marks, but not the + of a shogi promotion, e.g. Bxh8=++
+        let mut san = san;
+        while let Some(unchecked) = san.strip_suffix(['+', '#']) {
+            if unchecked.ends_with('=') {
+                break;
+            }
+            san = unchecked;
+        }
+        let (san, duck) = match san.split_once(',') {
+            Some((san, duck)) => (san, Some(variant.square(duck)?)),
+            None => (san, None),
+        };
+        if san.contains('@') {
+            return match parse(san, variant)? {
+                Input::Drop(kind, pos) if self.drop_piece(kind, &pos) => Ok(()),
+                _ => Err("illegal drop"),
+            };
+        }
+        let (body, promotion) = match san.split_once('=') {
+            Some((body, id)) => (
+                body,
+                id.chars().next().map(|id| variant.promotion_piece(id)),
+            ),
+            None => (san, None),
+        };
+        let body = match body {
+            "0-0" => "O-O",
+            "0-0-0" => "O-O-O",
+            body => body,
+        };
+        let mut found = None;
+        let (rows, cols) = self.state.board.shape();
+        for row in 0..rows as i32 {
+            for col in 0..cols as i32 {
+                let from = Vector(col, row);
+                if !matches!(self.state.get_piece(&from), Some(piece) if piece.owner == self.state.turn)
+                {
+                    continue;
+                }
+                let legal = self.rules.legal_moves(&self.state, &from);
+                for (to_row, line) in legal.0.iter().enumerate() {
+                    for (to_col, _) in line.iter().enumerate().filter(|(_, legal)| **legal) {
+                        let to = Vector(to_col as i32, to_row as i32);
+                        if san::move_body(&self.state, self.rules.as_ref(), &from, &to) != body {
+                            continue;
+                        }
+                        if found.is_some() {
+                            return Err("ambiguous move");
+                        }
+                        found = Some((from.clone(), to));
+                    }
+                }
+            }
+        }
+        let (from, to) = found.ok_or("illegal move")?;
+        // shogi and grand pieces only promote when the move says so, where they may stay as they are
+        let default = match variant.promotion_pieces().contains(&None) {
+            true => None,
+            false => variant.promotion_pieces()[0],
+        };
+        let offered = |promotion| match self.promotes(&self.state, &from, &to) {
+            true => self
+                .rules
+                .promotion_choices(&self.state, self.state.turn, &to)
+                .contains(&promotion),
+            false => true,
+        };
+        if promotion.is_some_and(|promotion| !offered(promotion))
+            || !self.set_promotion(promotion.unwrap_or(default))
+        {
+            return Err("cannot promote to that piece");
+        }
+        self.move_piece_unchecked(&from, &to);
+        match duck {
+            Some(duck) if !self.place_duck(&duck) => Err("cannot place the duck there"),
+            _ => Ok(()),
+        }
+    }
+    /*
+     * every move the player to move could make in 'state', always in the same order: moves by
+     * starting square then end square, row by row, a promoting move once for each piece it can
+     * promote to, then drops, by piece then square
+     */
+    pub fn move_list(&self, state: &GameState) -> Vec<Play> {
+        let mut plays = Vec::new();
+        let (rows, cols) = state.board.shape();
+        let squares = || {
+            (0..rows as i32).flat_map(move |row| (0..cols as i32).map(move |col| Vector(col, row)))
+        };
+        for from in squares() {
+            if !matches!(state.get_piece(&from), Some(piece) if piece.owner == state.turn) {
+                continue;
+            }
+            let legal = self.rules.legal_moves(state, &from);
+            for to in squares().filter(|to| legal[to]) {
+                match self.promotes(state, &from, &to) {
+                    true => {
+                        for promotion in self.rules.promotion_choices(state, state.turn, &to) {
+                            plays.push(Play::Move(from.clone(), to.clone(), promotion));
+                        }
+                    }
+                    false => plays.push(Play::Move(from.clone(), to, None)),
+                }
+            }
+        }
+        let mut hand = state.get_player(state.turn).hand.clone();
+        hand.sort_by_key(|kind| kind.to_char());
+        hand.dedup();
+        for kind in hand {
+            let droppable = self.rules.drop_squares(state, kind, state.turn);
+            for to in squares().filter(|to| droppable[to]) {
+                plays.push(Play::Drop(kind, to));
+            }
+        }
+        plays
+    }
+    // whether the piece at 'from' changes when it moves to 'to', so the move needs a promotion
+    fn promotes(&self, state: &GameState, from: &Vector, to: &Vector) -> bool {
+        let piece = match state.get_piece(from) {
+            Some(piece) if piece.owner != PlayerId::NEUTRAL => piece,
+            _ => return false,
+        };
+        let choices = self.rules.promotion_choices(state, piece.owner, to);
+        let mut after = state.clone();
+        after.get_player_mut(piece.owner).promotion =
+            match choices.iter().find(|kind| kind.is_some()) {
+                Some(kind) => *kind,
+                None => state.variant.promotion_pieces()[0],
+            };
+        self.rules.apply_move(&mut after, from, to);
+        matches!(after.get_piece(to), Some(moved) if moved.kind != piece.kind || moved.promoted != piece.promoted)
+    }
+    /*
+     * makes a move from move_list, in duck chess the duck still has to be placed after it
+     * returns whether the move was made
+     */
+    pub fn play(&mut self, play: &Play) -> bool {
+        if self.state.outcome.is_some() {
+            return false;
+        }
+        match play {
+            Play::Move(from, to, promotion) => {
+                if self.state.get_piece(from).is_none() {
+                    return false;
+                }
+                if self.promotes(&self.state, from, to)
+                    && !(self
+                        .rules
+                        .promotion_choices(&self.state, self.state.turn, to)
+                        .contains(promotion)
+                        && self.set_promotion(*promotion))
+                {
+                    return false;
+                }
+                self.move_piece_unchecked(from, to);
+                true
+            }
+            Play::Drop(kind, pos) => self.drop_piece(*kind, pos),
+        }
+    }
+    // the position after 'play' in 'state', before the turn ends or the duck is placed
+    pub fn after(&self, state: &GameState, play: &Play) -> GameState {
+        let mut after = state.clone();
+        match play {
+            Play::Move(from, to, promotion) => {
+                if let Some(piece) = state
+                    .get_piece(from)
+                    .filter(|_| self.promotes(state, from, to))
+                {
+                    after.get_player_mut(piece.owner).promotion = *promotion;
+                }
+                self.rules.apply_move(&mut after, from, to);
+            }
+            Play::Drop(kind, pos) => {
+                let piece = after.dropped_piece(*kind, after.turn, pos);
+                after.board[pos] = Some(piece);
+            }
+        }
+        after
+    }
     // print board to terminal for debug purposes
+    // in fog of war, only what the player to move can see is printed
     pub fn draw(&self) {
-        self.state.board.draw(&self.valid);
-        let prev_turn = (self.state.turn + self.state.players.len() - 2) % self.state.players.len();
-        let prev_player = &self.state.players[prev_turn];
+        match self.state.variant.fog_of_war() {
+            true => self.draw_for(self.state.turn),
+            false => self.draw_with(None),
+        }
+    }
+    // print only the squares 'viewer' can see, so players sharing a terminal can't cheat
+    pub fn draw_for(&self, viewer: PlayerId) {
+        self.draw_with(Some(viewer))
+    }
+    // the squares 'viewer' can see in fog of war, see GameState::visible_squares
+    pub fn visible_squares(&self, viewer: PlayerId) -> Matrix<bool> {
+        self.state.visible_squares(self.rules.as_ref(), viewer)
+    }
+    fn draw_with(&self, viewer: Option<PlayerId>) {
+        let visible = viewer.map(|viewer| self.visible_squares(viewer));
+        match &visible {
+            Some(visible) => self.state.board.draw_visible(&self.valid, visible),
+            None if self.state.variant.on_intersections() => {
+                self.state.board.draw_intersections(&self.valid)
+            }
+            None if self.state.variant.hexagonal() => self.state.board.draw_hex(&self.valid),
+            None if self.state.variant.three_dimensional() => {
+                let (width, stride) = (cube::SIZE as usize, cube::STRIDE as usize);
+                self.state.board.draw_levels(&self.valid, width, stride)
+            }
+            None if self.state.variant.shows_promotions() => {
+                self.state.board.draw_promotions(&self.valid)
+            }
+            None => self.state.board.draw(&self.valid),
+        }
+        let variant = self.state.variant;
+        let prev_turn = self.state.previous_player();
+        let prev_player = self.state.get_player(prev_turn);
+        // an enemy move is only seen if it ends somewhere visible
+        let seen = |recent_move: &Move| match (&visible, viewer) {
+            (Some(visible), Some(viewer)) => prev_turn == viewer || visible[&recent_move.end],
+            _ => true,
+        };
         match &prev_player.recent_move {
+            Some(recent_move) if !seen(recent_move) => println!("move: hidden"),
+            Some(recent_move) if recent_move.kind == MoveKind::Drop => println!(
+                "move: {}:{} dropped on {}",
+                recent_move.piece.kind,
+                recent_move.piece.owner,
+                variant.notation(&recent_move.end).unwrap(),
+            ),
             Some(recent_move) => println!(
                 "move: {}:{} from {} to {}",
-                recent_move.piece.id,
+                recent_move.piece.kind,
                 recent_move.piece.owner,
-                recent_move.start.to_notation().unwrap(),
-                recent_move.end.to_notation().unwrap(),
+                variant.notation(&recent_move.start).unwrap(),
+                variant.notation(&recent_move.end).unwrap(),
             ),
             None => (),
-        };
+        }
+        if self.state.variant.has_drops() {
+            for (i, player) in self.state.players.iter().enumerate() {
+                let hand: String = player.hand.iter().map(|kind| kind.to_char()).collect();
+                println!("player {} hand: [{}]", i + 1, hand);
+            }
+        }
         println!("player to move: {}", self.state.turn);
+        if self.state.placing_duck {
+            println!("player {} must place the duck", self.state.turn);
+        }
         println!("halfmove counter: {}", self.state.halfmove_counter);
+        if let Some(count) = &self.state.count {
+            let made = (self.state.halfmove_counter - count.start) / 2;
+            println!("counting: {} of {} moves", made, count.moves);
+        }
         match self.current_player().state {
             State::Checkmate => println!("player {} is in checkmate!", self.state.turn),
             State::Check => println!("player {} is in check!", self.state.turn),
             State::Stalemate => println!("player {} is in stalemate!", self.state.turn),
             State::None => (),
         };
+        if self
+            .state
+            .variant
+            .win_conditions()
+            .contains(&WinCondition::ThreeCheck)
+        {
+            for (i, player) in self.state.players.iter().enumerate() {
+                println!("player {} has been checked {} times", i + 1, player.checks);
+            }
+        }
+        match &self.state.outcome {
+            Some(Outcome::Win(player_id, reason)) => {
+                println!("player {player_id} wins by {reason}!")
+            }
+            Some(Outcome::Draw(reason)) => println!("draw by {reason}!"),
+            None => (),
+        }
+        match self.result() {
+            GameResult::Ongoing => (),
+            result => println!("result: {result}"),
+        }
+        if let Some(offer) = self.draw_offer {
+            println!("player {offer} offers a draw");
+        }
+    }
+}
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(&Setup::default())
     }
 }
+
 impl GameState {
-    pub fn get_piece(&self, pos: &Vector) -> &Piece {
-        &self.board[pos]
+    /*
+     * Parses a position in Forsyth-Edwards Notation, boards may be larger than 8x8
+     * Castling rights are given as KQkq (outermost R on that side) or as the file of the R (e.g. Jj)
+     * where each player stands is worked out by the rules of the Game played from it
+     */
+    pub fn from_fen(fen: &str, variant: Variant) -> Result<GameState, &'static str> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err("FEN must have 6 fields");
+        }
+        // crazyhouse positions end the placement with the pieces in hand, e.g. [QNp]
+        let (placement, pocket) = match fields[0].split_once('[') {
+            Some((placement, pocket)) => match pocket.strip_suffix(']') {
+                Some(pocket) => (placement, pocket),
+                None => return Err("pieces in hand must be closed with ]"),
+            },
+            None => (fields[0], ""),
+        };
+        let mut board = Board::from_fen(placement)?;
+        let (rows, cols) = board.shape();
+        let turn = match fields[1] {
+            "w" => PlayerId(1),
+            "b" => PlayerId(2),
+            _ => return Err("active color must be w or b"),
+        };
+        // P can only double move from their starting ranks
+        let directions = [Vector(0, 1), Vector(0, -1)];
+        pawns_moved(&mut board, &directions, variant);
+        castling_rights(&mut board, fields[2], &[PieceKind::Rook])?;
+        // players without a K (e.g. in horde) have nothing to keep out of check
+        let royal_king = |board: &Board, owner: PlayerId| match variant.royal_king() {
+            true => find_king(board, owner).ok(),
+            false => None,
+        };
+        let mut players = vec![
+            Player {
+                direction: Vector(0, 1),
+                recent_move: None,
+                state: State::None,
+                checks: 0,
+                king_pos: royal_king(&board, PlayerId(1)),
+                promotion: variant.promotion_pieces()[0],
+                hand: Vec::new(),
+                royal: Some(PieceKind::King),
+                promotion_ranks: far_ranks(&Vector(0, 1), (rows, cols), variant.promotion_zone()),
+            },
+            Player {
+                direction: Vector(0, -1),
+                recent_move: None,
+                state: State::None,
+                checks: 0,
+                king_pos: royal_king(&board, PlayerId(2)),
+                promotion: variant.promotion_pieces()[0],
+                hand: Vec::new(),
+                royal: Some(PieceKind::King),
+                promotion_ranks: far_ranks(&Vector(0, -1), (rows, cols), variant.promotion_zone()),
+            },
+        ];
+        for held in pocket.chars() {
+            if !held.is_ascii_alphabetic() {
+                return Err("unexpected character in pieces in hand");
+            }
+            let owner = if held.is_ascii_uppercase() { 1 } else { 2 };
+            players[owner - 1]
+                .hand
+                .push(PieceKind::from_char(held.to_ascii_uppercase()));
+        }
+        // an en passant square means the previous player just double moved a P past it
+        if fields[3] != "-" {
+            let passed = Vector::from_notation(fields[3])?;
+            let mover = turn.next(players.len());
+            let mover_index = mover.index().expect("players are numbered from 1");
+            let direction = players[mover_index].direction.clone();
+            players[mover_index].recent_move =
+                Some(double_move(&board, &passed, &direction, mover)?);
+        }
+        fields[4]
+            .parse::<u32>()
+            .map_err(|_| "halfmove clock must be a number")?;
+        let fullmove = match fields[5].parse::<u32>() {
+            Ok(fullmove) if fullmove >= 1 => fullmove,
+            _ => return Err("fullmove number must be a positive number"),
+        };
+        Ok(GameState {
+            board,
+            turn,
+            halfmove_counter: 2 * (fullmove - 1) + turn.0 as u32,
+            players,
+            variant,
+            captured: Vec::new(),
+            outcome: None,
+            placing_duck: false,
+            positions: Vec::new(),
+            castling: vec![PieceKind::Rook],
+            count: None,
+        })
+    }
+    // builds the starting position described by a setup, player 1 moves first, see from_fen
+    pub fn from_setup(setup: &Setup) -> GameState {
+        let (rows, cols) = setup.board.shape();
+        let royal_piece = |owner: PlayerId| {
+            let kind = setup.royal?;
+            for row in 0..rows as i32 {
+                for col in 0..cols as i32 {
+                    let piece = setup.board[&Vector(col, row)].as_ref();
+                    if piece.is_some_and(|piece| piece.kind == kind && piece.owner == owner) {
+                        return Some(Vector(col, row));
+                    }
+                }
+            }
+            None
+        };
+        // an en passant square means the last player just double moved a P past it
+        let last = setup.directions.len() - 1;
+        let passed_by = |passed: &Vector| {
+            double_move(
+                &setup.board,
+                passed,
+                &setup.directions[last],
+                PlayerId(last + 1),
+            )
+            .expect("setups check their en passant square")
+        };
+        let players = setup
+            .directions
+            .iter()
+            .enumerate()
+            .map(|(i, direction)| Player {
+                direction: direction.clone(),
+                recent_move: setup.passant.as_ref().filter(|_| i == last).map(passed_by),
+                state: State::None,
+                checks: 0,
+                king_pos: royal_piece(PlayerId(i + 1)),
+                promotion: setup.variant.promotion_pieces()[0],
+                hand: setup.hands[i].clone(),
+                royal: setup.royal,
+                promotion_ranks: setup.promotion_ranks[i].clone(),
+            })
+            .collect();
+        GameState {
+            board: setup.board.clone(),
+            turn: PlayerId(1),
+            halfmove_counter: 1,
+            players,
+            variant: setup.variant,
+            captured: Vec::new(),
+            outcome: None,
+            placing_duck: false,
+            positions: Vec::new(),
+            castling: setup.castling.clone(),
+            count: None,
+        }
     }
-    pub fn get_player(&self, player_id: usize) -> &Player {
-        &self.players[player_id - 1]
+    pub fn get_piece(&self, pos: &Vector) -> Option<&Piece> {
+        self.board[pos].as_ref()
+    }
+    // the piece at pos, for squares known to hold one
+    pub fn get_piece_mut(&mut self, pos: &Vector) -> &mut Piece {
+        self.board[pos].as_mut().expect("no piece on that square")
+    }
+    pub fn get_player(&self, player_id: PlayerId) -> &Player {
+        &self.players[player_id.index().expect("neutral pieces have no player")]
+    }
+    fn get_player_mut(&mut self, player_id: PlayerId) -> &mut Player {
+        &mut self.players[player_id.index().expect("neutral pieces have no player")]
     }
     pub fn in_bounds(&self, pos: &Vector) -> bool {
         self.board.in_bounds(pos)
     }
+    pub fn turn(&self) -> PlayerId {
+        self.turn
+    }
+    pub fn halfmove_counter(&self) -> u32 {
+        self.halfmove_counter
+    }
+    // the player who made the most recent move
+    pub fn previous_player(&self) -> PlayerId {
+        PlayerId((self.turn.0 + self.players.len() - 2) % self.players.len() + 1)
+    }
+    pub fn num_players(&self) -> usize {
+        self.players.len()
+    }
+    /*
+     * moves the piece at 'from' to 'to' for the player to move, with the move's side effects,
+     * without checking it is legal or ending the turn
+     */
+    pub fn apply_move<R: Ruleset + ?Sized>(&mut self, rules: &R, from: &Vector, to: &Vector) {
+        let piece = self.get_piece(from).expect("no piece to move").clone();
+        self.get_player_mut(self.turn).recent_move = Some(Move {
+            piece: piece.clone(),
+            start: from.clone(),
+            end: to.clone(),
+            kind: MoveKind::Standard,
+        });
+        // perform move side effects (e.g. K castle and P en passant)
+        let captures = self.captured.len();
+        rules.side_effects(self, from);
+        // modify board by swapping pieces around
+        rules.capture(self, to);
+        self.board[to] = self.board[from].take();
+        self.get_piece_mut(to).has_moved = true;
+        // perform capture side effects (e.g. atomic explosions)
+        if self.captured.len() > captures {
+            rules.capture_effects(self, to);
+        }
+        // update king_pos
+        let moved = self.get_piece(to).map(|piece| piece.kind);
+        if moved.is_some() && moved == self.get_player(self.turn).royal {
+            self.move_king(self.turn, to.clone());
+        }
+    }
+    // keeps track of player_id's royal K, if they have one
+    pub fn move_king(&mut self, player_id: PlayerId, pos: Vector) {
+        let player = self.get_player_mut(player_id);
+        if player.king_pos.is_some() {
+            player.king_pos = Some(pos);
+        }
+    }
+    // rank index of pos counted from player_id's side of the board
+    pub fn relative_rank(&self, player_id: PlayerId, pos: &Vector) -> i32 {
+        let (rows, _) = self.board.shape();
+        match self.get_player(player_id).direction {
+            Vector(_, 1) => pos.1,
+            _ => rows as i32 - 1 - pos.1,
+        }
+    }
+    // whether pos is inside player_id's xiangqi palace, the middle three files of their first three ranks
+    pub fn in_palace(&self, player_id: PlayerId, pos: &Vector) -> bool {
+        let (_, cols) = self.board.shape();
+        let files = cols as i32 / 2 - 1..=cols as i32 / 2 + 1;
+        self.in_bounds(pos) && files.contains(&pos.0) && self.relative_rank(player_id, pos) <= 2
+    }
+    // whether player_id has a royal K on the board
+    pub fn has_king(&self, player_id: PlayerId) -> bool {
+        let player = self.get_player(player_id);
+        match (&player.king_pos, player.royal) {
+            (Some(king_pos), Some(royal)) => {
+                let is_royal = |king: &Piece| king.kind == royal && king.owner == player_id;
+                self.get_piece(king_pos).is_some_and(is_royal)
+            }
+            _ => false,
+        }
+    }
+    // true once player_id's royal K has been taken off the board, e.g. by an atomic explosion
+    pub fn lost_king(&self, player_id: PlayerId) -> bool {
+        self.get_player(player_id).king_pos.is_some() && !self.has_king(player_id)
+    }
+    // whether pos is next to the K of any player other than player_id
+    pub fn touches_enemy_king(&self, player_id: PlayerId, pos: &Vector) -> bool {
+        PlayerId::all(self.players.len()).any(|enemy| match &self.get_player(enemy).king_pos {
+            Some(king_pos) => {
+                enemy != player_id
+                    && self.has_king(enemy)
+                    && (king_pos.0 - pos.0).abs() <= 1
+                    && (king_pos.1 - pos.1).abs() <= 1
+            }
+            None => false,
+        })
+    }
+    // whether player_id's K is threatened by any other player
+    pub fn king_threatened<R: Ruleset + ?Sized>(&self, rules: &R, player_id: PlayerId) -> bool {
+        let king_pos = match &self.get_player(player_id).king_pos {
+            Some(king_pos) => king_pos,
+            None => return false,
+        };
+        // a K reports what threatens it, any other royal piece has to be checked against every attacker
+        match self.get_piece(king_pos) {
+            Some(king) if king.kind == PieceKind::King => {
+                let (_, threatened) = rules.claim_squares(self, king_pos);
+                threatened[king_pos]
+            }
+            _ => rules.attacked(self, player_id, king_pos),
+        }
+    }
+    /*
+     * whether player_id's K survives their move unthreatened, destroying an enemy K ends the
+     * game first, so it doesn't matter if theirs is left threatened then
+     */
+    pub fn king_safe<R: Ruleset + ?Sized>(&self, rules: &R, player_id: PlayerId) -> bool {
+        let wins = PlayerId::all(self.num_players())
+            .any(|enemy| enemy != player_id && self.lost_king(enemy));
+        !self.lost_king(player_id) && (wins || !rules.king_threatened(self, player_id))
+    }
+    // whether any piece of a player other than player_id attacks pos
+    pub fn attacked<R: Ruleset + ?Sized>(
+        &self,
+        rules: &R,
+        player_id: PlayerId,
+        pos: &Vector,
+    ) -> bool {
+        let (rows, cols) = self.board.shape();
+        for row in 0..rows as i32 {
+            for col in 0..cols as i32 {
+                let attacker_pos = Vector(col, row);
+                let attacker = match self.get_piece(&attacker_pos) {
+                    Some(attacker) if attacker.owner != PlayerId::NEUTRAL => attacker,
+                    _ => continue,
+                };
+                if attacker.owner == player_id {
+                    continue;
+                }
+                // K returns the squares threatened by others instead of its own threats
+                let attacks = match attacker.kind {
+                    PieceKind::King => (pos.0 - col).abs() <= 1 && (pos.1 - row).abs() <= 1,
+                    _ => rules.claim_squares(self, &attacker_pos).1[pos],
+                };
+                if attacks {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    // whether moving the piece at start to end takes a piece, including en passant
+    pub fn is_capture(&self, start: &Vector, end: &Vector) -> bool {
+        let owner = self.get_piece(start).map(|piece| piece.owner);
+        let opposed = matches!(self.get_piece(end), Some(target) if Some(target.owner) != owner);
+        opposed || self.passant_victim(start, end).is_some()
+    }
+    /*
+     * the square of the P taken en passant if the P at start moves to the empty square end,
+     * that is the enemy P just behind end, which has just double moved
+     */
+    pub fn passant_victim(&self, start: &Vector, end: &Vector) -> Option<Vector> {
+        let piece = match self.get_piece(start) {
+            Some(piece) if piece.kind == PieceKind::Pawn && !piece.promoted => piece,
+            _ => return None,
+        };
+        if self.board[end].is_some() {
+            return None;
+        }
+        let victim = end.clone() + self.get_player(piece.owner).direction.clone() * -1;
+        if !self.in_bounds(&victim) {
+            return None;
+        }
+        let target = match self.get_piece(&victim) {
+            Some(target) if target.kind == PieceKind::Pawn => target,
+            _ => return None,
+        };
+        if target.owner == PlayerId::NEUTRAL || target.owner == piece.owner {
+            return None;
+        }
+        match &self.get_player(target.owner).recent_move {
+            Some(recent_move) if recent_move.end == victim && recent_move.square_dist() == 4 => {
+                Some(victim)
+            }
+            _ => None,
+        }
+    }
+    // whether any of player_id's pieces can make a capture
+    fn can_capture<R: Ruleset + ?Sized>(&self, rules: &R, player_id: PlayerId) -> bool {
+        let (rows, cols) = self.board.shape();
+        for row in 0..rows as i32 {
+            for col in 0..cols as i32 {
+                let start = Vector(col, row);
+                if !matches!(self.get_piece(&start), Some(piece) if piece.owner == player_id) {
+                    continue;
+                }
+                let (valid, _) = rules.claim_squares(self, &start);
+                for row in 0..rows as i32 {
+                    for col in 0..cols as i32 {
+                        let end = Vector(col, row);
+                        if valid[&end] && self.is_capture(&start, &end) {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+    // marks the squares the piece at pos can legally move to
+    pub fn legal_moves<R: Ruleset + ?Sized>(&self, rules: &R, pos: &Vector) -> Matrix<bool> {
+        let (rows, cols) = self.board.shape();
+        let piece = match self.get_piece(pos) {
+            Some(piece) if !self.placing_pieces(piece.owner) => piece,
+            _ => return Matrix::new(false, rows, cols),
+        };
+        let (valid, threat) = rules.claim_squares(self, pos); // ask what squares it wants
+        let (mut valid, _) = piece.speculate(valid, threat, pos, self, rules);
+        // a P which must promote but has nothing to promote to can't move there
+        if piece.kind == PieceKind::Pawn && !piece.promoted {
+            for row in 0..rows as i32 {
+                for col in 0..cols as i32 {
+                    let end = Vector(col, row);
+                    valid[&end] = valid[&end]
+                        && !(rules.promotes_on(self, piece.owner, &end)
+                            && rules.promotion_choices(self, piece.owner, &end).is_empty());
+                }
+            }
+        }
+        // when any capture is possible it must be made
+        if self.variant.forced_captures() && self.can_capture(rules, piece.owner) {
+            for row in 0..rows as i32 {
+                for col in 0..cols as i32 {
+                    let end = Vector(col, row);
+                    valid[&end] = valid[&end] && self.is_capture(pos, &end);
+                }
+            }
+        }
+        valid
+    }
+    // whether player_id still has pieces to place before any of theirs may move, as in sittuyin
+    pub fn placing_pieces(&self, player_id: PlayerId) -> bool {
+        self.variant.setup_phase() && !self.get_player(player_id).hand.is_empty()
+    }
+    // whether player_id has anything left on the board
+    pub fn has_pieces(&self, player_id: PlayerId) -> bool {
+        let mut pieces = self.board.0 .0.iter().flatten().flatten();
+        pieces.any(|piece| piece.owner == player_id)
+    }
+    // whether player_id has a piece of the given kind on the board
+    pub fn has_piece(&self, player_id: PlayerId, kind: PieceKind) -> bool {
+        let mut pieces = self.board.0 .0.iter().flatten().flatten();
+        pieces.any(|piece| piece.owner == player_id && piece.kind == kind)
+    }
+    /*
+     * the squares player_id can see in fog of war: those holding their own pieces,
+     * and those their pieces can move to or attack
+     */
+    pub fn visible_squares<R: Ruleset + ?Sized>(
+        &self,
+        rules: &R,
+        player_id: PlayerId,
+    ) -> Matrix<bool> {
+        let (rows, cols) = self.board.shape();
+        let mut visible = Matrix::new(false, rows, cols);
+        for row in 0..rows as i32 {
+            for col in 0..cols as i32 {
+                let pos = Vector(col, row);
+                let piece = match self.get_piece(&pos) {
+                    Some(piece) if piece.owner == player_id => piece,
+                    _ => continue,
+                };
+                visible[&pos] = true;
+                let (valid, threat) = rules.claim_squares(self, &pos);
+                // K returns the squares threatened by others instead of its own threats
+                let attacks = piece.kind != PieceKind::King;
+                for row in 0..rows as i32 {
+                    for col in 0..cols as i32 {
+                        let end = Vector(col, row);
+                        visible[&end] = visible[&end] || valid[&end] || (attacks && threat[&end]);
+                    }
+                }
+            }
+        }
+        visible
+    }
+    // whether player_id's K has a legal move onto the given rank
+    pub fn king_can_reach<R: Ruleset + ?Sized>(
+        &self,
+        rules: &R,
+        player_id: PlayerId,
+        rank: i32,
+    ) -> bool {
+        let king_pos = match &self.get_player(player_id).king_pos {
+            Some(king_pos) => king_pos,
+            None => return false,
+        };
+        let king = match self.get_piece(king_pos) {
+            Some(king) => king,
+            None => return false,
+        };
+        let (valid, threat) = rules.claim_squares(self, king_pos);
+        let (valid, _) = king.speculate(valid, threat, king_pos, self, rules);
+        valid.0[rank as usize].iter().any(|x| *x)
+    }
+    // remembers the current position, called once the player to move's state is known
+    pub fn record_position(&mut self) {
+        let key = self.position_key();
+        let check = matches!(
+            self.get_player(self.turn).state,
+            State::Check | State::Checkmate
+        );
+        self.positions.push(Position {
+            key,
+            turn: self.turn,
+            check,
+        });
+    }
+    // the pieces on each square and in each hand, which together with the turn tell positions apart
+    pub fn position_key(&self) -> String {
+        let mut key = String::new();
+        for square in self.board.0 .0.iter().flatten() {
+            match square {
+                Some(piece) => {
+                    key.push(piece.kind.to_char());
+                    key.push_str(&piece.owner.to_string());
+                    if piece.promoted {
+                        key.push('+');
+                    }
+                }
+                None => key.push_str(" 0"),
+            }
+        }
+        for player in &self.players {
+            let mut hand: Vec<char> = player.hand.iter().map(|kind| kind.to_char()).collect();
+            hand.sort();
+            key.push('/');
+            key.extend(hand);
+        }
+        key
+    }
+    /*
+     * removes the piece at pos from the board, rules where it goes into a hand put it there
+     * first, see Ruleset::capture, bughouse hands are filled by the partner board, see Bughouse
+     */
+    pub fn capture(&mut self, pos: &Vector) {
+        if let Some(piece) = self.board[pos].take() {
+            self.captured.push(piece);
+        }
+    }
+    // gives player_id a piece to drop
+    pub fn add_to_hand(&mut self, player_id: PlayerId, kind: PieceKind) {
+        self.get_player_mut(player_id).hand.push(kind);
+    }
+    // takes every piece out of player_id's hand
+    pub fn empty_hand(&mut self, player_id: PlayerId) {
+        self.get_player_mut(player_id).hand.clear();
+    }
+    // the piece placed on the board when player_id drops kind on pos
+    pub fn dropped_piece(&self, kind: PieceKind, player_id: PlayerId, pos: &Vector) -> Piece {
+        let rank = self.relative_rank(player_id, pos) as usize;
+        Piece {
+            kind,
+            owner: player_id,
+            // a P dropped on its starting rank may still double move, dropped R cannot castle
+            has_moved: kind != PieceKind::Pawn || !self.variant.pawn_ranks().contains(&rank),
+            promoted: false,
+        }
+    }
+    /*
+     * marks every square player_id could legally drop kind on
+     * the square must be empty, the rules must allow the drop there, see Ruleset::may_drop,
+     * and no drop may leave the K threatened
+     */
+    pub fn drop_squares<R: Ruleset + ?Sized>(
+        &self,
+        rules: &R,
+        kind: PieceKind,
+        player_id: PlayerId,
+    ) -> Matrix<bool> {
+        let (rows, cols) = self.board.shape();
+        let mut valid = Matrix::new(false, rows, cols);
+        if !self.get_player(player_id).hand.contains(&kind) {
+            return valid;
+        }
+        for row in 0..rows as i32 {
+            for col in 0..cols as i32 {
+                let pos = Vector(col, row);
+                if self.board[&pos].is_some() || !rules.may_drop(self, kind, player_id, &pos) {
+                    continue;
+                }
+                let mut speculation = self.clone();
+                speculation.board[&pos] = Some(self.dropped_piece(kind, player_id, &pos));
+                valid[&pos] = !rules.king_threatened(&speculation, player_id);
+            }
+        }
+        valid
+    }
     /*
      * Updates the end states of each player, defined in enum State
      */
-    pub fn update_check(&self, player_id: usize) -> State {
+    pub fn update_check<R: Ruleset + ?Sized>(&self, rules: &R, player_id: PlayerId) -> State {
+        // a K that has been destroyed can never escape
+        let royal = self.variant.royal_king();
+        if royal && self.lost_king(player_id) {
+            return State::Checkmate;
+        }
         let (rows, cols) = self.board.shape();
-        let mut is_threatened = false;
+        let is_threatened = royal && rules.king_threatened(self, player_id);
+        let placing = self.placing_pieces(player_id);
         let mut has_legal_moves = Vec::new();
         for row in 0..rows as i32 {
             for col in 0..cols as i32 {
                 let pos = Vector(col, row);
-                let piece = &self.board[&pos];
-                if piece.owner == player_id {
-                    let (valid, threat) = piece.claim_squares()(&pos, self);
-                    let (valid, threat) = piece.speculate(valid, threat, &pos, self);
-                    if piece.id == 'K' {
-                        is_threatened = threat[&pos];
-                    }
+                let piece = self.get_piece(&pos);
+                if let Some(piece) = piece.filter(|piece| piece.owner == player_id && !placing) {
+                    let (valid, threat) = rules.claim_squares(self, &pos);
+                    let (valid, _) = piece.speculate(valid, threat, &pos, self, rules);
                     let can_move = valid.0.into_iter().flatten().any(|x| x);
-                    println!("{:?} can {:?}", piece, can_move);
                     has_legal_moves.push(can_move);
                 }
             }
         }
-        println!("{:?}", is_threatened);
-        println!("{:?}", has_legal_moves);
+        // a drop that blocks a check is a way out of it
+        for kind in &self.get_player(player_id).hand {
+            let can_drop = rules
+                .drop_squares(self, *kind, player_id)
+                .0
+                .into_iter()
+                .flatten()
+                .any(|x| x);
+            has_legal_moves.push(can_drop);
+        }
         let no_legal_moves = has_legal_moves.into_iter().all(|x| !x);
-        println!("{:?}", no_legal_moves);
         match (is_threatened, no_legal_moves) {
             (true, true) => State::Checkmate,
             (true, false) => State::Check,
@@ -0,0 +1,103 @@
+use crate::board::Vector;
+
+/*
+ * Gliński's hexagonal board, stored in axial coordinates (q, r) as an 11x11 Matrix
+ * q counts files from the middle, r counts cells up a file, the cell (q, r) is stored at
+ * Vector(q + 5, r + 5), and the 30 corners where |q + r| > 5 are holes, leaving 91 cells
+ * files are lettered a to l skipping j, and ranks are counted from the bottom of each file,
+ * so rank 1 is a V with f1 at its point
+ */
+pub const RADIUS: i32 = 5;
+const FILES: &str = "abcdefghikl";
+
+// steps to the 6 cells sharing an edge, counterclockwise from forwards
+pub const ORTHOGONALS: [Vector; 6] = [
+    Vector(0, 1),
+    Vector(-1, 1),
+    Vector(-1, 0),
+    Vector(0, -1),
+    Vector(1, -1),
+    Vector(1, 0),
+];
+// steps to the 6 nearest cells sharing only a corner, each between two orthogonals
+pub const DIAGONALS: [Vector; 6] = [
+    Vector(-1, 2),
+    Vector(-2, 1),
+    Vector(-1, -1),
+    Vector(1, -2),
+    Vector(2, -1),
+    Vector(1, 1),
+];
+// one orthogonal step then one diagonal step away from where it started
+pub const KNIGHT_OFFSETS: [Vector; 12] = [
+    Vector(1, 2),
+    Vector(-1, 3),
+    Vector(-2, 3),
+    Vector(-3, 2),
+    Vector(-3, 1),
+    Vector(-2, -1),
+    Vector(-1, -2),
+    Vector(1, -3),
+    Vector(2, -3),
+    Vector(3, -2),
+    Vector(3, -1),
+    Vector(2, 1),
+];
+
+// whether pos is one of the 91 cells
+pub fn on_board(pos: &Vector) -> bool {
+    let (q, r) = (pos.0 - RADIUS, pos.1 - RADIUS);
+    q.abs() <= RADIUS && r.abs() <= RADIUS && (q + r).abs() <= RADIUS
+}
+
+// index of the first cell of a file
+fn file_start(col: i32) -> i32 {
+    (RADIUS - col).max(0)
+}
+
+pub fn to_notation(pos: &Vector) -> Result<String, &'static str> {
+    if !on_board(pos) {
+        return Err("not a cell of the hexagonal board");
+    }
+    let file = FILES.as_bytes()[pos.0 as usize] as char;
+    let rank = pos.1 - file_start(pos.0) + 1;
+    Ok(format!("{}{}", file, rank))
+}
+
+// inverse of to_notation, e.g. "f11"
+pub fn from_notation(notation: &str) -> Result<Vector, &'static str> {
+    let mut chars = notation.chars();
+    let col = match chars.next().and_then(|file| FILES.find(file)) {
+        Some(col) => col as i32,
+        None => return Err("file must be a letter from a to l, there is no j"),
+    };
+    let rank: i32 = match chars.as_str().parse() {
+        Ok(rank) if rank >= 1 => rank,
+        _ => return Err("rank must be a positive number"),
+    };
+    let pos = Vector(col, file_start(col) + rank - 1);
+    match on_board(&pos) {
+        true => Ok(pos),
+        false => Err("rank is past the end of the file"),
+    }
+}
+
+/*
+ * whether pos is one of the starting cells of a P moving in direction, from which it may
+ * double step, even if it has already moved
+ * player 1's P start on b1 c2 d3 e4 f5 g4 h3 i2 k1, player 2's on the mirror image
+ */
+pub fn pawn_start(pos: &Vector, direction: &Vector) -> bool {
+    // mirror player 2 onto player 1's side, flipping each file upside down
+    let pos = match direction {
+        Vector(_, 1) => pos.clone(),
+        _ => Vector(pos.0, 3 * RADIUS - pos.0 - pos.1),
+    };
+    (pos.0 <= RADIUS && pos.1 == RADIUS - 1) || (pos.0 >= RADIUS && pos.0 + pos.1 == 2 * RADIUS - 1)
+}
+
+// the two cells in the neighbouring files a P moving in direction captures on
+pub fn pawn_captures(direction: &Vector) -> [Vector; 2] {
+    let forwards = direction.1;
+    [Vector(1, forwards.min(0)), Vector(-1, forwards.max(0))]
+}
@@ -0,0 +1,62 @@
+use crate::board::Vector;
+use crate::piece::PieceKind;
+use crate::variant::Variant;
+
+// one thing typed at the terminal, see parse
+#[derive(Clone, PartialEq, Debug)]
+pub enum Input {
+    Select(Vector),                     // e.g. e4
+    Promote(Vector, Option<PieceKind>), // e.g. e8=N, or in shogi c7+ to promote and c7= to decline
+    Drop(PieceKind, Vector),            // e.g. P@e4
+}
+
+// squares are read in the variant's own notation, e.g. f11 on the hexagonal board
+pub fn parse(input: &str, variant: Variant) -> Result<Input, &'static str> {
+    match input.split_once('@') {
+        Some((id, square)) => {
+            let mut chars = id.chars();
+            match (chars.next(), chars.next()) {
+                (Some(id), None) if id.is_ascii_uppercase() => Ok(Input::Drop(
+                    PieceKind::from_char(id),
+                    variant.square(square)?,
+                )),
+                _ => Err("drops must name a single uppercase piece, e.g. P@e4"),
+            }
+        }
+        None if input.ends_with('+') => {
+            let square = &input[..input.len() - 1];
+            Ok(Input::Promote(
+                variant.square(square)?,
+                variant.promotion_piece('+'),
+            ))
+        }
+        None => match input.split_once('=') {
+            Some((square, "")) => Ok(Input::Promote(variant.square(square)?, None)),
+            Some((square, id)) => {
+                let mut chars = id.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(id), None) if id.is_ascii_uppercase() => Ok(Input::Promote(
+                        variant.square(square)?,
+                        variant.promotion_piece(id),
+                    )),
+                    _ => Err("promotions must name a single uppercase piece, e.g. e8=N"),
+                }
+            }
+            None => Ok(Input::Select(variant.square(input)?)),
+        },
+    }
+}
+
+impl Input {
+    // inverse of parse
+    pub fn notation(&self, variant: Variant) -> Result<String, &'static str> {
+        match self {
+            Input::Select(pos) => variant.notation(pos),
+            Input::Promote(pos, promotion) => match variant.promotion_id(*promotion) {
+                id @ ('+' | '=') => Ok(format!("{}{}", variant.notation(pos)?, id)),
+                id => Ok(format!("{}={}", variant.notation(pos)?, id)),
+            },
+            Input::Drop(id, pos) => Ok(format!("{}@{}", id, variant.notation(pos)?)),
+        }
+    }
+}
@@ -0,0 +1,19 @@
+pub mod annotation;
+pub mod board;
+pub mod bughouse;
+pub mod cube;
+pub mod database;
+pub mod encoding;
+pub mod game;
+pub mod hex;
+pub mod input;
+pub mod opening;
+pub mod outcome;
+pub mod pgn;
+pub mod piece;
+pub mod ruleset;
+pub mod san;
+pub mod setup;
+pub mod simul;
+pub mod tree;
+pub mod variant;
@@ -1,21 +1,35 @@
-mod board;
-mod game;
-mod piece;
-
+use rust_chess::annotation;
+use rust_chess::bughouse::Bughouse;
+use rust_chess::database::{Database, Query};
+use rust_chess::encoding::{Reader, Writer};
+use rust_chess::game::{self, GameState};
+use rust_chess::input::{parse, Input};
+use rust_chess::opening::{self, Classifier};
+use rust_chess::piece::{PieceKind, PlayerId};
+use rust_chess::setup::Setup;
+use rust_chess::simul::Simul;
+use rust_chess::variant::Variant;
+use std::env;
+use std::fs;
 use std::io::{self, BufRead};
 
-fn parse(input: Vec<u8>) -> board::Vector {
-    let file = input[0] - ('a' as u8);
-    let rank = input[1] - ('1' as u8);
-    board::Vector(file.into(), rank.into())
-}
-
 // repeatedly input squares in chess notation to interact with board
-// also can input 'rewind 6' for example to rewind 6 halfmoves earlier
-fn terminal_play() -> io::Result<()> {
+// also can input 'rewind 6' for example to rewind 6 halfmoves earlier, 'undo' and 'redo' take back
+// and replay moves, 'undo 3' three at once, and 'goto 10' jumps to the position after halfmove 10
+// pieces in hand are dropped with 'P@e4', and P promote to something other than Q with 'e8=N'
+// shogi pieces promote by default, 'c7=' moves without promoting
+// 'back' and 'forward' step through the moves, playing a new move after stepping back starts a
+// variation, 'promote' makes the current line the main line, 'delete' removes the last move and
+// every move after it, 'pgn' prints the game with all its variations, and 'opening' names its opening
+// 'comment' and 'nag' annotate the last move, e.g. 'nag !?' or 'comment only move [%cal Gd1h5]',
+// comments may hold [%clk], [%cal] and [%csl] commands as in PGN, 'save game.pgn' writes the game
+// 'save game.rcg' writes the game in the compact binary format, see encoding
+// 'resign' gives up the game for the player to move, 'draw' offers a draw, which the other player
+// answers with 'accept' or 'decline', or by moving, no moves can be made once the game is over
+// built with the serde feature, 'save game.json' saves everything, to resume with 'rust-chess load'
+fn terminal_play(mut game: game::Game) -> io::Result<()> {
     let mut history = Vec::new();
     let stdin = io::stdin();
-    let mut game = game::Game::new();
     game.draw();
     println!("Input a selection");
     loop {
@@ -28,20 +42,406 @@ fn terminal_play() -> io::Result<()> {
             }
             break Ok(());
         }
-        if input == "rewind" {
-            let input = stdin.lock().lines().next().unwrap().unwrap();
-            let num = input.into_bytes()[0] - ('1' as u8);
-            game.rewind(num.into());
-            println!("AFTER REWIND");
+        let mut words = input.split_whitespace();
+        let (command, argument) = (words.next().unwrap_or(""), words.next());
+        if command == "rewind" {
+            // the number of halfmoves may also be on the next line
+            let argument = match argument {
+                Some(argument) => argument.to_string(),
+                None => {
+                    let input = stdin.lock().lines().next().unwrap().unwrap();
+                    history.push(input.clone());
+                    input
+                }
+            };
+            match argument.trim().parse() {
+                Ok(halfmoves) => {
+                    game.rewind(halfmoves);
+                    println!("AFTER REWIND");
+                    game.draw();
+                }
+                Err(_) => println!("{argument} is not a number of halfmoves"),
+            }
+        } else if matches!(command, "undo" | "redo" | "goto") {
+            let number = match argument {
+                Some(argument) => argument.parse::<usize>().ok(),
+                None if command == "goto" => None,
+                None => Some(1),
+            };
+            let moved = match (command, number) {
+                (_, None) => {
+                    println!("{command} needs a number of halfmoves");
+                    continue;
+                }
+                ("goto", Some(ply)) => game.goto(ply),
+                ("undo", Some(count)) => (0..count).all(|_| game.undo()),
+                (_, Some(count)) => (0..count).all(|_| game.redo()),
+            };
+            if !moved {
+                println!("no more moves to {command}");
+            }
             game.draw();
-        } else {
-            let target = parse(input.into_bytes());
-            game.select(target);
+        } else if input == "back" || input == "forward" {
+            let moved = match input.as_str() {
+                "back" => game.back(),
+                _ => game.forward(),
+            };
+            if !moved {
+                println!("no move to go {input} to");
+            }
+            game.draw();
+        } else if input == "promote" {
+            game.promote_variation();
+            println!("{}", game.tree().movetext());
+        } else if input == "delete" {
+            if !game.delete_variation() {
+                println!("no move to delete");
+            }
+            game.draw();
+        } else if input == "pgn" {
+            print!("{}", game.pgn());
+        } else if matches!(input.as_str(), "resign" | "draw" | "accept" | "decline") {
+            let turn = game.state.turn();
+            let done = match input.as_str() {
+                "resign" => game.resign(turn),
+                "draw" => game.offer_draw(turn),
+                "accept" => game.accept_draw(turn),
+                _ => game.decline_draw(turn),
+            };
+            if !done {
+                match game.state.outcome {
+                    Some(_) => println!("the game is over"),
+                    None => println!("there is no draw offer to {input}"),
+                }
+            }
             game.draw();
+        } else if input == "opening" {
+            match Classifier::new().classify(&game) {
+                Some(opening) => println!("{} {}", opening.eco, opening.name),
+                None => println!("no known opening"),
+            }
+        } else if command == "nag" {
+            match annotation::nag(argument.unwrap_or("")) {
+                Ok(nag) => game.annotate().nags.push(nag),
+                Err(err) => println!("{err}"),
+            }
+        } else if let Some(comment) = input.strip_prefix("comment ") {
+            let variant = game.state.variant;
+            if let Err(err) = game.annotate().read_comment(comment, variant) {
+                println!("{err}");
+            }
+        } else if let Some(path) = input.strip_prefix("save ") {
+            #[cfg(feature = "serde")]
+            if path.ends_with(".json") {
+                game.save(path)?;
+                println!("saved to {path}");
+                continue;
+            }
+            match path.ends_with(".rcg") {
+                true => Writer::new(fs::File::create(path)?)?.write_game(&game)?,
+                false => fs::write(path, game.pgn())?,
+            }
+            println!("saved to {path}");
+        } else {
+            match parse(&input, game.state.variant) {
+                Ok(Input::Select(target)) if game.state.in_bounds(&target) => {
+                    game.set_promotion(game.state.variant.promotion_pieces()[0]);
+                    game.select(target);
+                    game.draw();
+                }
+                Ok(Input::Promote(target, id)) if game.state.in_bounds(&target) => {
+                    if game.set_promotion(id) {
+                        game.select(target);
+                    } else {
+                        println!("cannot promote to {}", game.state.variant.promotion_id(id));
+                    }
+                    game.draw();
+                }
+                Ok(Input::Drop(id, target)) => {
+                    if !game.drop_piece(id, &target) {
+                        println!("cannot drop {id} on {}", &input[2..]);
+                    }
+                    game.draw();
+                }
+                Ok(_) => println!("{input} is not on the board"),
+                Err(err) => println!("{err}"),
+            }
+        }
+    }
+}
+
+// same as terminal_play, but every input starts with the board number, e.g. '2 e4' or '1 P@e4'
+fn bughouse_play() -> io::Result<()> {
+    let mut history = Vec::new();
+    let stdin = io::stdin();
+    let mut bughouse = Bughouse::new();
+    bughouse.draw();
+    println!("Input a board number and a selection");
+    loop {
+        let input = stdin.lock().lines().next().unwrap().unwrap();
+        history.push(input.clone());
+        if input == "quit" {
+            println!("Input record:");
+            for record in history {
+                println!("{record}");
+            }
+            break Ok(());
+        }
+        let (board, selection) = match input.split_once(' ') {
+            Some(("1", selection)) => (0, selection),
+            Some(("2", selection)) => (1, selection),
+            _ => {
+                println!("start the input with the board number, 1 or 2");
+                continue;
+            }
+        };
+        match parse(selection, Variant::Bughouse) {
+            Ok(Input::Select(target)) if bughouse.board(board).state.in_bounds(&target) => {
+                bughouse.set_promotion(board, Some(PieceKind::Queen));
+                bughouse.select(board, target);
+                bughouse.draw();
+            }
+            Ok(Input::Promote(target, id)) if bughouse.board(board).state.in_bounds(&target) => {
+                if bughouse.set_promotion(board, id) {
+                    bughouse.select(board, target);
+                } else {
+                    println!("cannot promote to {}", Variant::Bughouse.promotion_id(id));
+                }
+                bughouse.draw();
+            }
+            Ok(Input::Drop(id, target)) => {
+                if !bughouse.drop_piece(board, id, &target) {
+                    println!("cannot drop {id} on {}", &selection[2..]);
+                }
+                bughouse.draw();
+            }
+            Ok(_) => println!("{input} is not on the board"),
+            Err(err) => println!("{err}"),
         }
     }
 }
 
+// same as bughouse_play, on any number of boards, the simul giver moving on one board at a time
+// 'score' prints the scoreboard, 'save simul.txt' writes the session to a file
+fn simul_play(mut simul: Simul) -> io::Result<()> {
+    let mut history = Vec::new();
+    let stdin = io::stdin();
+    let variant = simul.boards[0].state.variant;
+    simul.draw(simul.current());
+    println!("Input a board number and a selection");
+    loop {
+        let input = stdin.lock().lines().next().unwrap().unwrap();
+        history.push(input.clone());
+        if input == "quit" {
+            println!("Input record:");
+            for record in history {
+                println!("{record}");
+            }
+            break Ok(());
+        }
+        if input == "score" {
+            println!("score: {}", simul.scoreboard());
+            continue;
+        }
+        if let Some(path) = input.strip_prefix("save ") {
+            fs::write(path, simul.save())?;
+            println!("saved to {path}");
+            continue;
+        }
+        let board = match input.split_once(' ') {
+            Some((board, selection)) => match board.parse::<usize>() {
+                Ok(board @ 1..) => parse(selection, variant).map(|input| (board - 1, input)),
+                _ => Err("boards are numbered from 1"),
+            },
+            None => Err("start the input with the board number, e.g. 1 e4"),
+        };
+        match board.and_then(|(board, input)| simul.play(board, input).map(|_| board)) {
+            Ok(board) => simul.draw(board),
+            Err(err) => println!("{err}"),
+        }
+    }
+}
+
+/*
+ * rust-chess database <file> followed by one of
+ *   import <pgn file>              adds every game in the PGN file
+ *   search player=Morphy date=1858  games matching every key, also event= and result=
+ *   position [FEN]                 standard chess games reaching the position, the start by default
+ *   material KRPvKR                games reaching the material, player 1's pieces first
+ *   explore [FEN]                  the moves played from the position, and how those games ended
+ * a PGN file, ending .pgn, can be searched in the same way without importing it
+ */
+fn database_query(path: &str, args: &[String]) -> io::Result<()> {
+    let mut database = match path.ends_with(".pgn") {
+        true => Database::from_pgn(&fs::read_to_string(path)?)?,
+        false => Database::open(path)?,
+    };
+    let command = args.first().map(String::as_str).unwrap_or("");
+    let state = match args.get(1) {
+        Some(fen) if matches!(command, "position" | "explore") => {
+            match GameState::from_fen(fen, Variant::Standard) {
+                Ok(state) => state,
+                Err(err) => {
+                    println!("invalid FEN: {err}");
+                    return Ok(());
+                }
+            }
+        }
+        _ => game::Game::from_variant(Variant::Standard).state,
+    };
+    let games = match (command, args.get(1)) {
+        ("import", Some(pgn)) => {
+            let games = database.import_pgn(&fs::read_to_string(pgn)?)?;
+            println!("imported {games} games, {} in the database", database.len());
+            return Ok(());
+        }
+        ("search", _) => {
+            let mut query = Query::default();
+            for arg in &args[1..] {
+                match arg.split_once('=') {
+                    Some(("player", value)) => query.player = Some(value.to_string()),
+                    Some(("event", value)) => query.event = Some(value.to_string()),
+                    Some(("date", value)) => query.date = Some(value.to_string()),
+                    Some(("result", value)) => query.result = Some(value.to_string()),
+                    _ => {
+                        println!("search by player=, event=, date= or result=");
+                        return Ok(());
+                    }
+                }
+            }
+            database.search(&query)
+        }
+        ("position", _) => database.with_position(&state),
+        ("material", Some(signature)) => database.with_material(signature),
+        ("explore", _) => {
+            if let Some(opening) = Classifier::new().position(&state) {
+                println!("{} {}", opening.eco, opening.name);
+            }
+            for continuation in opening::explore(&state, &database) {
+                println!(
+                    "{}: {} games, {:.0}% white, {:.0}% drawn, {:.0}% black",
+                    continuation.san,
+                    continuation.games,
+                    continuation.white,
+                    continuation.draws,
+                    continuation.black
+                );
+            }
+            return Ok(());
+        }
+        _ => {
+            println!("database commands are import, search, position, material and explore");
+            return Ok(());
+        }
+    };
+    for id in games {
+        let tag = |name| database.tag(id, name).unwrap_or("?");
+        println!(
+            "{}. {} - {}, {}, {}, {}",
+            id + 1,
+            tag("White"),
+            tag("Black"),
+            tag("Event"),
+            tag("Date"),
+            database.result(id).unwrap_or("*")
+        );
+    }
+    Ok(())
+}
+
+// usage: rust-chess [variant] [FEN], rust-chess setup <file>, or rust-chess pgn <file>
+// rust-chess load <file> resumes a game saved as JSON, when built with the serde feature
+// rust-chess archive <file> [number] opens a game from a binary archive, the first by default
+// e.g. rust-chess capablanca, or rust-chess grand "r8r/... w - - 0 1"
+// see Setup for what a setup file looks like
+// rust-chess simul <boards> [variant] starts a simul, rust-chess simul <file> resumes a saved one
+// rust-chess database <file> ... searches a game database, see database_query
 fn main() -> io::Result<()> {
-    terminal_play()
+    let args: Vec<String> = env::args().collect();
+    if let (Some("simul"), Some(arg)) = (args.get(1).map(String::as_str), args.get(2)) {
+        let simul = match arg.parse::<usize>() {
+            Ok(boards @ 1..) => {
+                let variant = args.get(3).map(|name| Variant::from_name(name));
+                match variant {
+                    Some(None) => Err("unknown variant"),
+                    Some(Some(variant)) => Ok(Simul::new(variant, boards, PlayerId(1))),
+                    None => Ok(Simul::new(Variant::Standard, boards, PlayerId(1))),
+                }
+            }
+            _ => Simul::load(&fs::read_to_string(arg)?),
+        };
+        return match simul {
+            Ok(simul) => simul_play(simul),
+            Err(err) => {
+                println!("invalid simul: {err}");
+                Ok(())
+            }
+        };
+    }
+    if let (Some("database"), Some(path)) = (args.get(1).map(String::as_str), args.get(2)) {
+        return database_query(path, &args[3..]);
+    }
+    #[cfg(feature = "serde")]
+    if let (Some("load"), Some(path)) = (args.get(1).map(String::as_str), args.get(2)) {
+        return terminal_play(game::Game::load(path)?);
+    }
+    if let (Some("archive"), Some(path)) = (args.get(1).map(String::as_str), args.get(2)) {
+        let number = match args.get(3).map(|number| number.parse::<usize>()) {
+            Some(Ok(number @ 1..)) => number,
+            Some(_) => {
+                println!("games are numbered from 1");
+                return Ok(());
+            }
+            None => 1,
+        };
+        return match Reader::new(fs::File::open(path)?)?.nth(number - 1) {
+            Some(game) => terminal_play(game?),
+            None => {
+                println!("the archive has fewer than {number} games");
+                Ok(())
+            }
+        };
+    }
+    if let (Some("pgn"), Some(path)) = (args.get(1).map(String::as_str), args.get(2)) {
+        return match game::Game::from_pgn(&fs::read_to_string(path)?) {
+            Ok(game) => terminal_play(game),
+            Err(err) => {
+                println!("invalid PGN: {err}");
+                Ok(())
+            }
+        };
+    }
+    if let (Some("setup"), Some(path)) = (args.get(1).map(String::as_str), args.get(2)) {
+        return match Setup::parse(&fs::read_to_string(path)?) {
+            Ok(setup) => terminal_play(game::Game::new(&setup)),
+            Err(err) => {
+                println!("invalid setup: {err}");
+                Ok(())
+            }
+        };
+    }
+    let variant = match args.get(1) {
+        Some(name) => match Variant::from_name(name) {
+            Some(variant) => variant,
+            None => {
+                println!("unknown variant {name}");
+                return Ok(());
+            }
+        },
+        None => Variant::Standard,
+    };
+    if variant == Variant::Bughouse {
+        return bughouse_play();
+    }
+    let game = match args.get(2) {
+        Some(fen) => match GameState::from_fen(fen, variant) {
+            Ok(state) => game::Game::from_state(state),
+            Err(err) => {
+                println!("invalid FEN: {err}");
+                return Ok(());
+            }
+        },
+        None => game::Game::from_variant(variant),
+    };
+    terminal_play(game)
 }
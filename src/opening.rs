@@ -0,0 +1,347 @@
+use crate::database::{self, Database};
+use crate::game::{Game, GameState};
+use crate::variant::Variant;
+use std::collections::HashMap;
+
+// a named opening, and the moves reaching it from the start of a standard chess game
+#[derive(Clone, PartialEq, Debug)]
+pub struct Opening {
+    pub eco: &'static str, // Encyclopaedia of Chess Openings code, A00 to E99
+    pub name: &'static str,
+    pub moves: &'static str, // in SAN, separated by spaces
+}
+
+const fn opening(eco: &'static str, name: &'static str, moves: &'static str) -> Opening {
+    Opening { eco, name, moves }
+}
+
+/*
+ * the openings games are classified by, the main line of each ECO group and the best known
+ * openings and variations within them, in ECO order
+ * this is only part of ECO, which has 500 codes and thousands of named lines: many codes are
+ * missing, and a game in a line which isn't listed is named after the last listed position it
+ * reached, e.g. a Meran is classified as the Semi-Slav Defence, D43
+ */
+pub const OPENINGS: [Opening; 130] = [
+    opening("A00", "Polish Opening", "b4"),
+    opening("A00", "Grob Opening", "g4"),
+    opening("A00", "Van 't Kruijs Opening", "e3"),
+    opening("A01", "Nimzowitsch-Larsen Attack", "b3"),
+    opening("A02", "Bird's Opening", "f4"),
+    opening("A03", "Bird's Opening, Dutch Variation", "f4 d5"),
+    opening("A04", "Réti Opening", "Nf3"),
+    opening("A05", "Réti Opening", "Nf3 Nf6"),
+    opening("A06", "Réti Opening", "Nf3 d5"),
+    opening("A07", "King's Indian Attack", "Nf3 d5 g3"),
+    opening("A09", "Réti Opening", "Nf3 d5 c4"),
+    opening("A10", "English Opening", "c4"),
+    opening("A13", "English Opening", "c4 e6"),
+    opening("A15", "English Opening, Anglo-Indian Defence", "c4 Nf6"),
+    opening("A16", "English Opening, Anglo-Indian Defence", "c4 Nf6 Nc3"),
+    opening("A20", "English Opening, King's English", "c4 e5"),
+    opening("A22", "English Opening, King's English", "c4 e5 Nc3 Nf6"),
+    opening("A25", "English Opening, Closed", "c4 e5 Nc3 Nc6"),
+    opening("A30", "English Opening, Symmetrical Variation", "c4 c5"),
+    opening("A40", "Queen's Pawn Game", "d4"),
+    opening("A40", "Englund Gambit", "d4 e5"),
+    opening("A41", "Queen's Pawn Game", "d4 d6"),
+    opening("A43", "Old Benoni Defence", "d4 c5"),
+    opening("A45", "Indian Defence", "d4 Nf6"),
+    opening("A45", "Trompowsky Attack", "d4 Nf6 Bg5"),
+    opening("A46", "Indian Defence", "d4 Nf6 Nf3"),
+    opening("A48", "East Indian Defence", "d4 Nf6 Nf3 g6"),
+    opening("A50", "Indian Defence", "d4 Nf6 c4"),
+    opening("A51", "Budapest Gambit", "d4 Nf6 c4 e5"),
+    opening("A52", "Budapest Gambit", "d4 Nf6 c4 e5 dxe5 Ng4"),
+    opening("A53", "Old Indian Defence", "d4 Nf6 c4 d6"),
+    opening("A56", "Benoni Defence", "d4 Nf6 c4 c5"),
+    opening("A57", "Benko Gambit", "d4 Nf6 c4 c5 d5 b5"),
+    opening("A60", "Modern Benoni", "d4 Nf6 c4 c5 d5 e6"),
+    opening("A80", "Dutch Defence", "d4 f5"),
+    opening("A84", "Dutch Defence", "d4 f5 c4"),
+    opening("B00", "Nimzowitsch Defence", "e4 Nc6"),
+    opening("B00", "Owen's Defence", "e4 b6"),
+    opening("B01", "Scandinavian Defence", "e4 d5"),
+    opening("B02", "Alekhine's Defence", "e4 Nf6"),
+    opening("B03", "Alekhine's Defence", "e4 Nf6 e5 Nd5 d4"),
+    opening("B06", "Modern Defence", "e4 g6"),
+    opening("B07", "Pirc Defence", "e4 d6 d4 Nf6"),
+    opening("B10", "Caro-Kann Defence", "e4 c6"),
+    opening(
+        "B12",
+        "Caro-Kann Defence, Advance Variation",
+        "e4 c6 d4 d5 e5",
+    ),
+    opening(
+        "B13",
+        "Caro-Kann Defence, Exchange Variation",
+        "e4 c6 d4 d5 exd5 cxd5",
+    ),
+    opening("B15", "Caro-Kann Defence", "e4 c6 d4 d5 Nc3"),
+    opening(
+        "B18",
+        "Caro-Kann Defence, Classical Variation",
+        "e4 c6 d4 d5 Nc3 dxe4 Nxe4 Bf5",
+    ),
+    opening("B20", "Sicilian Defence", "e4 c5"),
+    opening(
+        "B21",
+        "Sicilian Defence, Smith-Morra Gambit",
+        "e4 c5 d4 cxd4 c3",
+    ),
+    opening("B22", "Sicilian Defence, Alapin Variation", "e4 c5 c3"),
+    opening("B23", "Sicilian Defence, Closed", "e4 c5 Nc3"),
+    opening("B27", "Sicilian Defence", "e4 c5 Nf3"),
+    opening("B30", "Sicilian Defence", "e4 c5 Nf3 Nc6"),
+    opening(
+        "B30",
+        "Sicilian Defence, Rossolimo Variation",
+        "e4 c5 Nf3 Nc6 Bb5",
+    ),
+    opening(
+        "B33",
+        "Sicilian Defence, Open",
+        "e4 c5 Nf3 Nc6 d4 cxd4 Nxd4 Nf6",
+    ),
+    opening("B40", "Sicilian Defence", "e4 c5 Nf3 e6"),
+    opening("B50", "Sicilian Defence", "e4 c5 Nf3 d6"),
+    opening("B54", "Sicilian Defence, Open", "e4 c5 Nf3 d6 d4 cxd4 Nxd4"),
+    opening(
+        "B56",
+        "Sicilian Defence, Open",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3",
+    ),
+    opening(
+        "B70",
+        "Sicilian Defence, Dragon Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 g6",
+    ),
+    opening(
+        "B80",
+        "Sicilian Defence, Scheveningen Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 e6",
+    ),
+    opening(
+        "B90",
+        "Sicilian Defence, Najdorf Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6",
+    ),
+    opening("C00", "French Defence", "e4 e6"),
+    opening(
+        "C01",
+        "French Defence, Exchange Variation",
+        "e4 e6 d4 d5 exd5",
+    ),
+    opening("C02", "French Defence, Advance Variation", "e4 e6 d4 d5 e5"),
+    opening(
+        "C03",
+        "French Defence, Tarrasch Variation",
+        "e4 e6 d4 d5 Nd2",
+    ),
+    opening("C10", "French Defence", "e4 e6 d4 d5 Nc3"),
+    opening(
+        "C11",
+        "French Defence, Classical Variation",
+        "e4 e6 d4 d5 Nc3 Nf6",
+    ),
+    opening(
+        "C15",
+        "French Defence, Winawer Variation",
+        "e4 e6 d4 d5 Nc3 Bb4",
+    ),
+    opening("C20", "King's Pawn Game", "e4 e5"),
+    opening("C21", "Centre Game", "e4 e5 d4 exd4"),
+    opening("C23", "Bishop's Opening", "e4 e5 Bc4"),
+    opening("C25", "Vienna Game", "e4 e5 Nc3"),
+    opening("C30", "King's Gambit", "e4 e5 f4"),
+    opening(
+        "C31",
+        "King's Gambit Declined, Falkbeer Countergambit",
+        "e4 e5 f4 d5",
+    ),
+    opening("C33", "King's Gambit Accepted", "e4 e5 f4 exf4"),
+    opening("C40", "King's Knight Opening", "e4 e5 Nf3"),
+    opening("C40", "Latvian Gambit", "e4 e5 Nf3 f5"),
+    opening("C41", "Philidor Defence", "e4 e5 Nf3 d6"),
+    opening("C42", "Petrov's Defence", "e4 e5 Nf3 Nf6"),
+    opening("C44", "King's Pawn Game", "e4 e5 Nf3 Nc6"),
+    opening("C44", "Ponziani Opening", "e4 e5 Nf3 Nc6 c3"),
+    opening("C44", "Scotch Game", "e4 e5 Nf3 Nc6 d4"),
+    opening("C45", "Scotch Game", "e4 e5 Nf3 Nc6 d4 exd4 Nxd4"),
+    opening("C46", "Three Knights Game", "e4 e5 Nf3 Nc6 Nc3"),
+    opening("C47", "Four Knights Game", "e4 e5 Nf3 Nc6 Nc3 Nf6"),
+    opening("C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"),
+    opening("C50", "Italian Game, Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5"),
+    opening("C51", "Evans Gambit", "e4 e5 Nf3 Nc6 Bc4 Bc5 b4"),
+    opening(
+        "C53",
+        "Italian Game, Classical Variation",
+        "e4 e5 Nf3 Nc6 Bc4 Bc5 c3",
+    ),
+    opening("C55", "Two Knights Defence", "e4 e5 Nf3 Nc6 Bc4 Nf6"),
+    opening(
+        "C57",
+        "Two Knights Defence, Knight Attack",
+        "e4 e5 Nf3 Nc6 Bc4 Nf6 Ng5",
+    ),
+    opening("C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"),
+    opening("C62", "Ruy Lopez, Steinitz Defence", "e4 e5 Nf3 Nc6 Bb5 d6"),
+    opening(
+        "C63",
+        "Ruy Lopez, Schliemann Defence",
+        "e4 e5 Nf3 Nc6 Bb5 f5",
+    ),
+    opening("C65", "Ruy Lopez, Berlin Defence", "e4 e5 Nf3 Nc6 Bb5 Nf6"),
+    opening(
+        "C68",
+        "Ruy Lopez, Exchange Variation",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6",
+    ),
+    opening("C70", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4"),
+    opening(
+        "C77",
+        "Ruy Lopez, Morphy Defence",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6",
+    ),
+    opening(
+        "C78",
+        "Ruy Lopez, Morphy Defence",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O",
+    ),
+    opening(
+        "C80",
+        "Ruy Lopez, Open Variation",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Nxe4",
+    ),
+    opening(
+        "C84",
+        "Ruy Lopez, Closed",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7",
+    ),
+    opening(
+        "C89",
+        "Ruy Lopez, Marshall Attack",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7 Re1 b5 Bb3 O-O c3 d5",
+    ),
+    opening("D00", "Queen's Pawn Game", "d4 d5"),
+    opening("D00", "London System", "d4 d5 Bf4"),
+    opening("D02", "Queen's Pawn Game", "d4 d5 Nf3"),
+    opening("D06", "Queen's Gambit", "d4 d5 c4"),
+    opening(
+        "D07",
+        "Queen's Gambit Declined, Chigorin Defence",
+        "d4 d5 c4 Nc6",
+    ),
+    opening(
+        "D08",
+        "Queen's Gambit Declined, Albin Countergambit",
+        "d4 d5 c4 e5",
+    ),
+    opening("D10", "Slav Defence", "d4 d5 c4 c6"),
+    opening("D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"),
+    opening("D30", "Queen's Gambit Declined", "d4 d5 c4 e6"),
+    opening("D30", "Queen's Gambit Declined", "d4 d5 c4 e6 Nf3"),
+    opening("D35", "Queen's Gambit Declined", "d4 d5 c4 e6 Nc3 Nf6"),
+    opening("D37", "Queen's Gambit Declined", "d4 d5 c4 e6 Nc3 Nf6 Nf3"),
+    opening("D43", "Semi-Slav Defence", "d4 d5 c4 e6 Nc3 Nf6 Nf3 c6"),
+    opening("D80", "Grünfeld Defence", "d4 Nf6 c4 g6 Nc3 d5"),
+    opening("E00", "Indian Defence", "d4 Nf6 c4 e6"),
+    opening("E01", "Catalan Opening", "d4 Nf6 c4 e6 g3"),
+    opening("E10", "Indian Defence", "d4 Nf6 c4 e6 Nf3"),
+    opening("E11", "Bogo-Indian Defence", "d4 Nf6 c4 e6 Nf3 Bb4+"),
+    opening("E12", "Queen's Indian Defence", "d4 Nf6 c4 e6 Nf3 b6"),
+    opening("E20", "Nimzo-Indian Defence", "d4 Nf6 c4 e6 Nc3 Bb4"),
+    opening("E60", "King's Indian Defence", "d4 Nf6 c4 g6"),
+    opening("E61", "King's Indian Defence", "d4 Nf6 c4 g6 Nc3 Bg7"),
+    opening("E70", "King's Indian Defence", "d4 Nf6 c4 g6 Nc3 Bg7 e4 d6"),
+    opening(
+        "E80",
+        "King's Indian Defence, Sämisch Variation",
+        "d4 Nf6 c4 g6 Nc3 Bg7 e4 d6 f3",
+    ),
+    opening(
+        "E90",
+        "King's Indian Defence",
+        "d4 Nf6 c4 g6 Nc3 Bg7 e4 d6 Nf3",
+    ),
+    opening(
+        "E97",
+        "King's Indian Defence, Classical Variation",
+        "d4 Nf6 c4 g6 Nc3 Bg7 e4 d6 Nf3 O-O Be2 e5 O-O Nc6",
+    ),
+];
+
+/*
+ * Names the opening of standard chess games, by the positions they reach rather than the order
+ * of their moves, so 1. Nf3 d5 2. d4 is found to be the Queen's Pawn Game of 1. d4 d5 2. Nf3
+ * only the openings in OPENINGS are known, which are part of ECO rather than all of it, so the
+ * name found is the closest listed opening, not always the most specific one
+ */
+pub struct Classifier {
+    positions: HashMap<u64, &'static Opening>, // by database::position_hash
+}
+
+impl Default for Classifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Classifier {
+    pub fn new() -> Classifier {
+        let mut positions = HashMap::new();
+        for opening in &OPENINGS {
+            let mut game = Game::from_variant(Variant::Standard);
+            for san in opening.moves.split_whitespace() {
+                game.play_san(san).expect("openings are legal");
+            }
+            // the first opening listed keeps a position reached by two
+            positions
+                .entry(database::position_hash(&game.state))
+                .or_insert(opening);
+        }
+        Classifier { positions }
+    }
+    // the opening the position is, None if it isn't one
+    pub fn position(&self, state: &GameState) -> Option<&'static Opening> {
+        self.positions.get(&database::position_hash(state)).copied()
+    }
+    // the last opening reached along the game's main line, None if it never left the book's start
+    pub fn classify(&self, game: &Game) -> Option<&'static Opening> {
+        let tree = game.tree();
+        tree.mainline()
+            .iter()
+            .rev()
+            .find_map(|id| self.position(&tree.node(*id)?.state))
+    }
+}
+
+// a move played from a position, and the share of the games after it each player won
+#[derive(Clone, PartialEq, Debug)]
+pub struct Continuation {
+    pub san: String,
+    pub games: usize,
+    pub white: f64, // percentage won by player 1
+    pub draws: f64,
+    pub black: f64, // percentage won by player 2
+}
+
+/*
+ * the moves played from the position in the database's games, most played first
+ * games still in progress count towards the total but not the percentages, which can add up to less than 100
+ * a collection of PGN can be explored as Database::from_pgn
+ */
+pub fn explore(state: &GameState, database: &Database) -> Vec<Continuation> {
+    let percent = |count: usize, games: usize| 100.0 * count as f64 / games as f64;
+    database
+        .move_stats(state)
+        .into_iter()
+        .map(|stats| Continuation {
+            white: percent(stats.wins, stats.games),
+            draws: percent(stats.draws, stats.games),
+            black: percent(stats.losses, stats.games),
+            san: stats.san,
+            games: stats.games,
+        })
+        .collect()
+}
@@ -0,0 +1,235 @@
+use crate::board::Vector;
+use crate::game::{GameState, State};
+use crate::piece::{PieceKind, PlayerId};
+use crate::ruleset::Ruleset;
+use std::fmt;
+
+// how a finished game ended
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Outcome {
+    Win(PlayerId, &'static str), // winning player, and how they won
+    Draw(&'static str),
+}
+
+impl Outcome {
+    // how the game was won or drawn, e.g. checkmate
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Outcome::Win(_, reason) | Outcome::Draw(reason) => reason,
+        }
+    }
+}
+
+// how a game stands, finished or not, see Game::result
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameResult {
+    Win(PlayerId),
+    Draw,
+    Ongoing,
+}
+
+impl GameResult {
+    pub fn of(outcome: Option<&Outcome>) -> GameResult {
+        match outcome {
+            Some(Outcome::Win(player_id, _)) => GameResult::Win(*player_id),
+            Some(Outcome::Draw(_)) => GameResult::Draw,
+            None => GameResult::Ongoing,
+        }
+    }
+    // as written in PGN, None for a win by a player after the second, which PGN can't write
+    pub fn pgn(self) -> Option<&'static str> {
+        match self {
+            GameResult::Win(PlayerId(1)) => Some("1-0"),
+            GameResult::Win(PlayerId(2)) => Some("0-1"),
+            GameResult::Win(_) => None,
+            GameResult::Draw => Some("1/2-1/2"),
+            GameResult::Ongoing => Some("*"),
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameResult::Draw => write!(f, "½-½"),
+            GameResult::Ongoing => write!(f, "ongoing"),
+            GameResult::Win(player_id) => match self.pgn() {
+                Some(result) => write!(f, "{result}"),
+                None => write!(f, "player {player_id} wins"),
+            },
+        }
+    }
+}
+
+// every way a game can end, so a saved Outcome can be read back as one of them
+pub const REASONS: [&str; 16] = [
+    "destroying the king",
+    "checkmate",
+    "stalemate",
+    "three checks",
+    "king of the hill",
+    "both kings reaching the last rank",
+    "reaching the last rank",
+    "losing all pieces",
+    "capturing every piece",
+    "capturing the king",
+    "having no legal moves",
+    "perpetual check",
+    "sennichite",
+    "counting rules",
+    "resignation",
+    "agreement",
+];
+
+// an Outcome as saved, its reason is matched against REASONS when it is read back
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+enum SavedOutcome {
+    Win(PlayerId, String),
+    Draw(String),
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Outcome {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Outcome, D::Error> {
+        use serde::de::Error;
+        let known = |reason: String| {
+            REASONS
+                .into_iter()
+                .find(|known| *known == reason)
+                .ok_or_else(|| D::Error::custom(format!("unknown outcome {reason}")))
+        };
+        match SavedOutcome::deserialize(deserializer)? {
+            SavedOutcome::Win(player_id, reason) => Ok(Outcome::Win(player_id, known(reason)?)),
+            SavedOutcome::Draw(reason) => Ok(Outcome::Draw(known(reason)?)),
+        }
+    }
+}
+
+// ways to end a game, each variant declares the ones it is played with
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WinCondition {
+    Checkmate,     // checkmating (or destroying) the enemy K wins, stalemate draws
+    ThreeCheck,    // checking the enemy K for the third time wins
+    KingOfTheHill, // moving the K onto one of the four centre squares wins
+    RacingKings,   // first K to the last rank wins, unless the other K follows on the next move
+    Antichess,     // losing every piece, or having no legal moves, wins
+    Annihilation,  // capturing every enemy piece wins
+    KingCapture,   // there is no check, capturing the enemy K wins
+    NoMoves,       // having no legal moves on your turn wins
+    Stalemate,     // having no legal moves on your turn loses, even out of check
+    Sennichite,    // the fourth repetition of a position draws, unless one side checked throughout
+    Counting, // makruk's counting rules, once no P are left the game is drawn if it drags on, see Count
+}
+
+impl WinCondition {
+    // called after every move, returns Some once the game has ended under this condition
+    pub fn evaluate<R: Ruleset + ?Sized>(&self, game: &GameState, rules: &R) -> Option<Outcome> {
+        let (rows, cols) = game.board.shape();
+        let mover = game.previous_player();
+        match self {
+            WinCondition::Checkmate => {
+                for player_id in PlayerId::all(game.num_players()) {
+                    match game.get_player(player_id).state {
+                        State::Checkmate if game.lost_king(player_id) => {
+                            return Some(Outcome::Win(mover, "destroying the king"));
+                        }
+                        State::Checkmate => return Some(Outcome::Win(mover, "checkmate")),
+                        State::Stalemate if player_id == game.turn() => {
+                            return Some(Outcome::Draw("stalemate"));
+                        }
+                        _ => (),
+                    }
+                }
+                None
+            }
+            WinCondition::ThreeCheck => PlayerId::all(game.num_players())
+                .any(|player_id| game.get_player(player_id).checks >= 3)
+                .then_some(Outcome::Win(mover, "three checks")),
+            WinCondition::KingOfTheHill => {
+                let hill = |pos: &Vector| {
+                    (rows as i32 / 2 - 1..=rows as i32 / 2).contains(&pos.1)
+                        && (cols as i32 / 2 - 1..=cols as i32 / 2).contains(&pos.0)
+                };
+                PlayerId::all(game.num_players())
+                    .find(|player_id| match &game.get_player(*player_id).king_pos {
+                        Some(king_pos) => game.has_king(*player_id) && hill(king_pos),
+                        None => false,
+                    })
+                    .map(|player_id| Outcome::Win(player_id, "king of the hill"))
+            }
+            // player 1 moves first, so player 2 gets one last move to draw by reaching the goal
+            WinCondition::RacingKings => {
+                let goal = rows as i32 - 1;
+                let arrived = |player_id: PlayerId| match &game.get_player(player_id).king_pos {
+                    Some(king_pos) => king_pos.1 == goal,
+                    None => false,
+                };
+                let (first, second) = (PlayerId(1), PlayerId(2));
+                match (arrived(first), arrived(second)) {
+                    (true, true) => Some(Outcome::Draw("both kings reaching the last rank")),
+                    (false, true) => Some(Outcome::Win(second, "reaching the last rank")),
+                    (true, false)
+                        if game.turn() == second && game.king_can_reach(rules, second, goal) =>
+                    {
+                        None
+                    }
+                    (true, false) => Some(Outcome::Win(first, "reaching the last rank")),
+                    (false, false) => None,
+                }
+            }
+            WinCondition::Antichess => {
+                let turn = game.turn();
+                let has_pieces = game.has_pieces(turn);
+                match game.get_player(turn).state {
+                    State::Stalemate if !has_pieces => {
+                        Some(Outcome::Win(turn, "losing all pieces"))
+                    }
+                    State::Stalemate => Some(Outcome::Win(turn, "stalemate")),
+                    _ => None,
+                }
+            }
+            WinCondition::Annihilation => PlayerId::all(game.num_players())
+                .any(|player_id| !game.has_pieces(player_id))
+                .then_some(Outcome::Win(mover, "capturing every piece")),
+            WinCondition::KingCapture => PlayerId::all(game.num_players())
+                .any(|player_id| !game.has_piece(player_id, PieceKind::King))
+                .then_some(Outcome::Win(mover, "capturing the king")),
+            WinCondition::NoMoves => match game.get_player(game.turn()).state {
+                State::Stalemate => Some(Outcome::Win(game.turn(), "having no legal moves")),
+                _ => None,
+            },
+            WinCondition::Sennichite => {
+                let current = game.positions.last()?;
+                let positions = game.positions.iter();
+                let repeats = positions.filter(|position| *position == current).count();
+                if repeats < 4 {
+                    return None;
+                }
+                // a player in check every time they moved during the cycle wins
+                let first = game
+                    .positions
+                    .iter()
+                    .position(|position| position == current)?;
+                let cycle = &game.positions[first..];
+                for player_id in PlayerId::all(game.num_players()) {
+                    let mut faced = cycle.iter().filter(|position| position.turn == player_id);
+                    if faced.all(|position| position.check) {
+                        return Some(Outcome::Win(player_id, "perpetual check"));
+                    }
+                }
+                Some(Outcome::Draw("sennichite"))
+            }
+            WinCondition::Counting => {
+                let count = game.count.as_ref()?;
+                (game.halfmove_counter() - count.start >= 2 * count.moves)
+                    .then_some(Outcome::Draw("counting rules"))
+            }
+            WinCondition::Stalemate => match game.get_player(game.turn()).state {
+                State::Stalemate => Some(Outcome::Win(mover, "stalemate")),
+                _ => None,
+            },
+        }
+    }
+}
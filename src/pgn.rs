@@ -0,0 +1,258 @@
+use crate::annotation;
+use crate::game::{Game, GameState};
+use crate::outcome::GameResult;
+use crate::variant::Variant;
+
+/*
+ * Portable game notation, the tags describing a game followed by its moves, e.g.
+ *
+ *     [Event "?"]
+ *     ...
+ *     [Result "*"]
+ *
+ *     1. e4 e5 (1... c5! {the Sicilian}) 2. Nf3 $14 {[%clk 0:04:51]} *
+ *
+ * variants are named by a Variant tag, and read from a FEN tag if the game didn't start from the
+ * variant's usual position
+ */
+
+// a game's tags in the order they're written, e.g. ("White", "Morphy")
+pub type Tags = Vec<(String, String)>;
+
+// the tags every PGN starts with, and what they are when nothing is known
+const ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/*
+ * the game as PGN, with every variation and annotation
+ * the result is that of the main line if it has ended, otherwise the Result tag the game was read with
+ */
+pub fn write(game: &Game) -> String {
+    let tree = game.tree();
+    let result = result(game);
+    let mut pgn = String::new();
+    for (name, default) in ROSTER {
+        let value = match name {
+            "Result" => result,
+            _ => tag(&game.tags, name).unwrap_or(default),
+        };
+        pgn.push_str(&write_tag(name, value));
+    }
+    let variant = game.state.variant;
+    if variant != Variant::Standard {
+        pgn.push_str(&write_tag("Variant", variant.name()));
+    }
+    for (name, value) in &game.tags {
+        if name != "Variant" && ROSTER.iter().all(|(roster, _)| roster != name) {
+            pgn.push_str(&write_tag(name, value));
+        }
+    }
+    let movetext = tree.movetext();
+    match movetext.is_empty() {
+        true => pgn.push_str(&format!("\n{result}\n")),
+        false => pgn.push_str(&format!("\n{movetext} {result}\n")),
+    }
+    pgn
+}
+
+// the result, 1-0, 0-1, 1/2-1/2 or * if it's still going, how the game was ended by
+// Game::finish if it was, otherwise how the main line ends
+pub fn result(game: &Game) -> &str {
+    let tree = game.tree();
+    let last = match game.finished() {
+        Some(finished) => finished,
+        None => *tree.mainline().last().unwrap(),
+    };
+    match GameResult::of(tree.node(last).unwrap().state.outcome.as_ref()) {
+        GameResult::Ongoing => tag(&game.tags, "Result").unwrap_or("*"),
+        result => result.pgn().unwrap_or("*"),
+    }
+}
+
+// the value of the tag 'name', if the game has one
+pub fn tag<'a>(tags: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    tags.iter()
+        .find(|(tag, _)| tag == name)
+        .map(|(_, value)| value.as_str())
+}
+
+// sets the tag 'name', replacing its value if the game already has one
+pub fn set_tag(tags: &mut Tags, name: &str, value: &str) {
+    match tags.iter_mut().find(|(tag, _)| tag == name) {
+        Some((_, old)) => *old = value.to_string(),
+        None => tags.push((name.to_string(), value.to_string())),
+    }
+}
+
+fn write_tag(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{name} \"{value}\"]\n")
+}
+
+/*
+ * reads a game, with its variations and annotations, the game is left at the end of the main line
+ * moves are read as SAN, see Game::play_san, and the first move of each variation replaces the
+ * move before it
+ */
+pub fn read(text: &str) -> Result<Game, &'static str> {
+    let (tags, movetext) = read_tags(text)?;
+    let variant = match tag(&tags, "Variant") {
+        Some(name) => Variant::from_name(name).ok_or("unknown variant")?,
+        None => Variant::Standard,
+    };
+    let mut game = start(variant, tags)?;
+    // the move each open variation was played instead of, to carry on from once it closes
+    let mut variations = Vec::new();
+    for token in tokens(movetext)? {
+        match token {
+            Token::Move(san) => game.play_san(san)?,
+            Token::Nag(nag) => game.annotate().nags.push(nag),
+            Token::Comment(comment) => game.annotate().read_comment(comment, variant)?,
+            Token::Open => {
+                let current = game.tree().current();
+                let node = game.tree().node(current);
+                let parent = node.and_then(|node| node.parent);
+                game.goto_node(parent.ok_or("a variation must follow the move it replaces")?);
+                variations.push(current);
+            }
+            Token::Close => {
+                game.goto_node(variations.pop().ok_or("unopened variation")?);
+            }
+        }
+    }
+    if !variations.is_empty() {
+        return Err("unclosed variation");
+    }
+    game.goto_node(*game.tree().mainline().last().unwrap());
+    Ok(game)
+}
+
+// a game of 'variant' with 'tags', from the position in its FEN tag if it has one
+pub fn start(variant: Variant, tags: Tags) -> Result<Game, &'static str> {
+    let mut game = match tag(&tags, "FEN") {
+        Some(fen) => Game::from_state(GameState::from_fen(fen, variant)?),
+        None => Game::from_variant(variant),
+    };
+    game.tags = tags;
+    Ok(game)
+}
+
+// the games in a file of many, each starting with its tags
+pub fn split(text: &str) -> Vec<&str> {
+    let mut games = Vec::new();
+    let (mut start, mut in_movetext, mut in_comment) = (0, false, false);
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if !in_comment && line.starts_with('[') && in_movetext {
+            games.push(&text[start..offset]);
+            start = offset;
+            in_movetext = false;
+        }
+        for c in line.chars() {
+            match c {
+                '{' => in_comment = true,
+                '}' => in_comment = false,
+                _ => {}
+            }
+        }
+        in_movetext |= in_comment || !(line.trim().is_empty() || line.starts_with('['));
+        offset += line.len();
+    }
+    if !text[start..].trim().is_empty() {
+        games.push(&text[start..]);
+    }
+    games
+}
+
+// the tags at the start of the text, e.g. [White "Morphy"], and the movetext after them
+fn read_tags(text: &str) -> Result<(Tags, &str), &'static str> {
+    let mut tags = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(tag) = rest.strip_prefix('[') {
+        let (name, tag) = tag
+            .split_once('"')
+            .ok_or("tags are written [Name \"value\"]")?;
+        let mut value = String::new();
+        let mut chars = tag.char_indices();
+        let end = loop {
+            match chars.next() {
+                Some((_, '\\')) => value.extend(chars.next().map(|(_, c)| c)),
+                Some((end, '"')) => break end,
+                Some((_, c)) => value.push(c),
+                None => return Err("unclosed tag value"),
+            }
+        };
+        let close = tag[end..].find(']').ok_or("unclosed tag")?;
+        tags.push((name.trim().to_string(), value));
+        rest = tag[end + close + 1..].trim_start();
+    }
+    Ok((tags, rest))
+}
+
+// a part of the movetext, move numbers and results are left out
+enum Token<'a> {
+    Move(&'a str),
+    Nag(u8),
+    Comment(&'a str), // without its braces
+    Open,             // (, a variation starts
+    Close,            // ), a variation ends
+}
+
+fn tokens(movetext: &str) -> Result<Vec<Token<'_>>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut rest = movetext.trim_start();
+    while let Some(first) = rest.chars().next() {
+        match first {
+            '{' => {
+                let end = rest.find('}').ok_or("unclosed comment")?;
+                tokens.push(Token::Comment(&rest[1..end]));
+                rest = &rest[end + 1..];
+            }
+            // the rest of the line is a comment
+            ';' => {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                tokens.push(Token::Comment(&rest[1..end]));
+                rest = &rest[end..];
+            }
+            '(' | ')' => {
+                tokens.push(if first == '(' {
+                    Token::Open
+                } else {
+                    Token::Close
+                });
+                rest = &rest[1..];
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "{}();".contains(c))
+                    .unwrap_or(rest.len());
+                let mut word = &rest[..end];
+                rest = &rest[end..];
+                // move numbers, e.g. 12. or 12..., may be written against the move
+                let unnumbered = word.trim_start_matches(|c: char| c.is_ascii_digit());
+                if unnumbered.starts_with('.') {
+                    word = unnumbered.trim_start_matches('.');
+                }
+                let result = ["1-0", "0-1", "1/2-1/2", "*"].contains(&word);
+                if word.starts_with('$') {
+                    tokens.push(Token::Nag(annotation::nag(word)?));
+                } else if !word.is_empty() && !result {
+                    let san = word.trim_end_matches(['!', '?']);
+                    tokens.push(Token::Move(san));
+                    if san.len() < word.len() {
+                        tokens.push(Token::Nag(annotation::nag(&word[san.len()..])?));
+                    }
+                }
+            }
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
@@ -1,12 +1,138 @@
 use crate::board::{Matrix, Vector};
-use crate::game::{GameState, State};
+use crate::cube;
+use crate::game::GameState;
+use crate::hex;
+use crate::ruleset::Ruleset;
+use std::fmt;
+
+// what a piece is, variants' own pieces (e.g. the archbishop A, or shogi's gold G) are named
+// by their letter, which the variant decides the moves of
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PieceKind {
+    King,
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+    Pawn,
+    Duck, // the neutral blocker of duck chess
+    Hole, // a square missing from the board, set up by a custom setup
+    Custom(char),
+}
+
+// which player controls a piece, players are numbered from 1 in the order they move
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerId(pub usize);
 
 // each piece keeps track of what kind it is (K, Q, R, ...) and which player controls it (1, 2, 3, ...)
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece {
-    pub id: char,
-    pub owner: usize, // indexes into a vector of players
+    pub kind: PieceKind,
+    pub owner: PlayerId,
     pub has_moved: bool,
+    pub promoted: bool, // promoted from a P, demotes back to a P when dropped in crazyhouse
+}
+
+impl PieceKind {
+    // the kind named by an uppercase letter, e.g. from FEN
+    // the duck and holes are never read as letters, see Piece::duck and Piece::hole
+    pub fn from_char(id: char) -> PieceKind {
+        match id {
+            'K' => PieceKind::King,
+            'Q' => PieceKind::Queen,
+            'R' => PieceKind::Rook,
+            'B' => PieceKind::Bishop,
+            'N' => PieceKind::Knight,
+            'P' => PieceKind::Pawn,
+            id => PieceKind::Custom(id),
+        }
+    }
+    // inverse of from_char, the duck is D and holes are #
+    pub fn to_char(self) -> char {
+        match self {
+            PieceKind::King => 'K',
+            PieceKind::Queen => 'Q',
+            PieceKind::Rook => 'R',
+            PieceKind::Bishop => 'B',
+            PieceKind::Knight => 'N',
+            PieceKind::Pawn => 'P',
+            PieceKind::Duck => 'D',
+            PieceKind::Hole => '#',
+            PieceKind::Custom(id) => id,
+        }
+    }
+}
+
+impl fmt::Display for PieceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl PlayerId {
+    // owns the duck and holes
+    pub const NEUTRAL: PlayerId = PlayerId(0);
+    // index into a vector of players, None for pieces which belong to nobody
+    pub fn index(self) -> Option<usize> {
+        self.0.checked_sub(1)
+    }
+    // the player who moves after this one, out of 'players'
+    pub fn next(self, players: usize) -> PlayerId {
+        PlayerId(self.0 % players + 1)
+    }
+    // every player, in the order they move
+    pub fn all(players: usize) -> impl Iterator<Item = PlayerId> {
+        (1..=players).map(PlayerId)
+    }
+}
+
+impl fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+const ORTHOGONALS: [Vector; 4] = [Vector(0, 1), Vector(1, 0), Vector(0, -1), Vector(-1, 0)];
+const DIAGONALS: [Vector; 4] = [
+    Vector(1, 1),   // NE
+    Vector(1, -1),  // SE
+    Vector(-1, -1), // SW
+    Vector(-1, 1),  // NW
+];
+//counterclockwise from positive x-axis
+const KNIGHT_OFFSETS: [Vector; 8] = [
+    Vector(2, 1),
+    Vector(1, 2),
+    Vector(-1, 2),
+    Vector(-2, 1),
+    Vector(-2, -1),
+    Vector(-1, -2),
+    Vector(1, -2),
+    Vector(2, -1),
+];
+
+// a piece's moves (return_tuple.0) and threats (return_tuple.1) from a position
+type Steps = fn(&Piece, &Vector, &GameState) -> (Matrix<bool>, Matrix<bool>);
+// the same for the piece at a position, by the rules the game is played by, see Piece::claim_squares
+pub type Squares<R> = fn(&Vector, &GameState, &R) -> (Matrix<bool>, Matrix<bool>);
+// what the piece at a position does as it moves or captures, by the rules the game is played by
+pub type Effects<R> = fn(&Vector, &mut GameState, &R);
+
+// turns (sideways, forwards) offsets into board vectors for a piece moving in direction forward
+fn relative(offsets: &[(i32, i32)], forward: &Vector) -> Vec<Vector> {
+    let right = Vector(-forward.1, forward.0);
+    offsets
+        .iter()
+        .map(|(x, y)| Vector(x * right.0 + y * forward.0, x * right.1 + y * forward.1))
+        .collect()
+}
+
+// the piece being asked for its squares, only called for squares holding one
+fn piece_at<'a>(game: &'a GameState, pos: &Vector) -> &'a Piece {
+    game.get_piece(pos).expect("only pieces claim squares")
 }
 
 fn init_masks(game: &GameState) -> (Matrix<bool>, Matrix<bool>) {
@@ -18,26 +144,45 @@ fn init_masks(game: &GameState) -> (Matrix<bool>, Matrix<bool>) {
 }
 
 impl Piece {
-    pub fn new() -> Piece {
+    pub fn new(kind: PieceKind, owner: PlayerId) -> Piece {
         Piece {
-            id: ' ',
-            owner: 0,
+            kind,
+            owner,
             has_moved: false,
+            promoted: false,
         }
     }
+    // the neutral blocker of duck chess
+    pub fn duck() -> Piece {
+        Self::new(PieceKind::Duck, PlayerId::NEUTRAL)
+    }
+    pub fn is_duck(&self) -> bool {
+        self.kind == PieceKind::Duck
+    }
+    // a square missing from the board, set up by a custom setup
+    pub fn hole() -> Piece {
+        Self::new(PieceKind::Hole, PlayerId::NEUTRAL)
+    }
+    pub fn is_hole(&self) -> bool {
+        self.kind == PieceKind::Hole
+    }
     /*
-     * Each piece id has an associated closure which marks all valid moves (return_tuple.0)
+     * Each piece kind has an associated closure which marks all valid moves (return_tuple.0)
      * Each piece except for K declares which squares it threatens (return_tuple.1)
      * K instead declares which squares it is threatened by (for end-game condition calculation)
+     * these are the pieces of standard chess and the variants played with its pieces, rulesets
+     * for boards and pieces of their own claim squares by hex_squares, cube_squares,
+     * shogi_squares, makruk_squares or xiangqi_squares instead, see Ruleset::claim_squares
      */
-    pub fn claim_squares(
-        &self,
-    ) -> impl FnOnce(&Vector, &GameState) -> (Matrix<bool>, Matrix<bool>) {
-        match self.id {
-            'K' => |pos: &Vector, game: &GameState| -> (Matrix<bool>, Matrix<bool>) {
-                let this = game.get_piece(pos);
-                let (mut valid, mut threat) = init_masks(&game);
-                let offsets = vec![
+    pub fn claim_squares<R: Ruleset + ?Sized>(&self) -> Squares<R> {
+        match self.kind {
+            PieceKind::King => |pos: &Vector, game: &GameState, rules: &R| {
+                piece_at(game, pos).king_squares(pos, game, rules, false)
+            },
+            PieceKind::Queen => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                let directions = vec![
                     Vector(-1, -1),
                     Vector(-1, 0),
                     Vector(-1, 1),
@@ -47,262 +192,594 @@ impl Piece {
                     Vector(1, 0),
                     Vector(1, 1),
                 ];
-                // check whether a square is threatened by other players
-                let mut threatened = threat.clone(); // accumulated threat matrix of all pieces
-                let mut or_assign = |pos: &Vector| {
-                    // to prevent infinite recursion
-                    if game.get_piece(pos).id == 'K' {
-                        for offset in &offsets {
-                            let target = pos.clone() + offset.clone();
-                            if game.board.in_bounds(&target) {
-                                threatened[&target] = true;
-                            }
-                        }
-                        return;
-                    }
-                    // handle non-king pieces
-                    let (_, threat) = game.board[pos].claim_squares()(pos, game);
-                    for row in 0..threat.0.len() as i32 {
-                        for col in 0..threat.0[0].len() as i32 {
-                            let pos = Vector(col, row);
-                            threatened[&pos] = threatened[&pos] || threat[&pos];
-                        }
-                    }
-                };
-                for row in 0..game.board.0 .0.len() as i32 {
-                    for col in 0..game.board.0 .0[0].len() as i32 {
-                        let attacker = game.get_piece(&Vector(col, row));
-                        let attacked = game.get_piece(pos);
-                        if attacker.owner != attacked.owner {
-                            or_assign(&Vector(col, row));
-                        }
-                    }
-                }
-                // mark standard moves
-                for offset in offsets {
-                    let target = pos.clone() + offset;
-                    if game.board.in_bounds(&target) && !threatened[&target] {
-                        this.attack(target, &game, &mut valid, Some(&mut threat));
-                    }
-                }
-                // mark castles
+                this.extend(pos, directions, game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            PieceKind::Rook => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, ORTHOGONALS.to_vec(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            PieceKind::Bishop => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, DIAGONALS.to_vec(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            PieceKind::Knight => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.leap(pos, KNIGHT_OFFSETS.to_vec(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            // archbishop, moves as B or N
+            PieceKind::Custom('A') => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, DIAGONALS.to_vec(), game, &mut valid, &mut threat);
+                this.leap(pos, KNIGHT_OFFSETS.to_vec(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            // chancellor, moves as R or N
+            PieceKind::Custom('C') => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, ORTHOGONALS.to_vec(), game, &mut valid, &mut threat);
+                this.leap(pos, KNIGHT_OFFSETS.to_vec(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            // moves one step forwards, or two from its first move, and captures diagonally forwards
+            PieceKind::Pawn => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let forward = game.get_player(this.owner).direction.clone();
+                let captures = relative(&[(1, 1), (-1, 1)], &forward);
+                this.pawn_squares(pos, game, vec![forward], !this.has_moved, captures)
+            },
+            _ => |_: &Vector, game: &GameState, _: &R| {
+                let (valid, threat) = init_masks(game);
+                (valid, threat)
+            },
+        }
+    }
+    // the pieces of hexagonal chess, moving between the cells laid out in hex
+    pub fn hex_squares<R: Ruleset + ?Sized>(&self) -> Squares<R> {
+        match self.kind {
+            PieceKind::King => |pos: &Vector, game: &GameState, rules: &R| {
+                let this = piece_at(game, pos);
+                this.royal_squares(pos, game, rules, Piece::hex_king_squares)
+            },
+            PieceKind::Queen => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(
+                    pos,
+                    hex::ORTHOGONALS.to_vec(),
+                    game,
+                    &mut valid,
+                    &mut threat,
+                );
+                this.extend(pos, hex::DIAGONALS.to_vec(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            PieceKind::Rook => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(
+                    pos,
+                    hex::ORTHOGONALS.to_vec(),
+                    game,
+                    &mut valid,
+                    &mut threat,
+                );
+                (valid, threat)
+            },
+            PieceKind::Bishop => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, hex::DIAGONALS.to_vec(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            PieceKind::Knight => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.leap(
+                    pos,
+                    hex::KNIGHT_OFFSETS.to_vec(),
+                    game,
+                    &mut valid,
+                    &mut threat,
+                );
+                (valid, threat)
+            },
+            // moves forwards along its file, captures forwards into the neighbouring files
+            PieceKind::Pawn => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
                 let unit_vec = game.get_player(this.owner).direction.clone();
-                let can_castle = |direction: &Vector| {
-                    // def of castle here
-                    if game.in_bounds(&(pos.clone() + unit_vec.clone() * -1)) || this.has_moved {
-                        return false;
-                    }
-                    let friendly_rook =
-                        |piece: &Piece| piece.id == 'R' && piece.owner == this.owner;
-                    let mut target = pos.clone();
-                    loop {
-                        let piece = game.get_piece(&target);
-                        if threatened[&target] {
-                            return false;
-                        } else if friendly_rook(piece) {
-                            return !piece.has_moved;
-                        } else if piece.id != ' ' && target != *pos {
-                            return false;
-                        }
-                        target += direction.clone();
-                        if !game.in_bounds(&target) {
-                            return false;
-                        }
-                    }
-                };
-                let right = Vector(-1 * unit_vec.1, unit_vec.0);
-                let left = right.clone() * -1;
-                if can_castle(&right) {
-                    let target = pos.clone() + right * 2;
-                    this.attack(target, &game, &mut valid, Some(&mut threat));
-                }
-                if can_castle(&left) {
-                    let target = pos.clone() + left * 2;
-                    this.attack(target, &game, &mut valid, Some(&mut threat));
+                let captures = hex::pawn_captures(&unit_vec).to_vec();
+                let double = hex::pawn_start(pos, &unit_vec);
+                this.pawn_squares(pos, game, vec![unit_vec], double, captures)
+            },
+            _ => self.claim_squares(),
+        }
+    }
+    // the pieces of raumschach, moving between the cells laid out in cube
+    pub fn cube_squares<R: Ruleset + ?Sized>(&self) -> Squares<R> {
+        match self.kind {
+            PieceKind::King => |pos: &Vector, game: &GameState, rules: &R| {
+                let this = piece_at(game, pos);
+                this.royal_squares(pos, game, rules, Piece::cube_king_squares)
+            },
+            PieceKind::Queen => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, cube::orthogonals(), game, &mut valid, &mut threat);
+                this.extend(pos, cube::diagonals(), game, &mut valid, &mut threat);
+                this.extend(pos, cube::triagonals(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            PieceKind::Rook => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, cube::orthogonals(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            PieceKind::Bishop => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, cube::diagonals(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            // unicorn, slides through the corners of cells
+            PieceKind::Custom('U') => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, cube::triagonals(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            PieceKind::Knight => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.leap(pos, cube::knight_offsets(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            // moves forwards or upwards without double moves, captures as in cube::pawn_captures
+            PieceKind::Pawn => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let unit_vec = game.get_player(this.owner).direction.clone();
+                let moves = cube::pawn_moves(&unit_vec).to_vec();
+                let captures = cube::pawn_captures(&unit_vec).to_vec();
+                this.pawn_squares(pos, game, moves, false, captures)
+            },
+            _ => self.claim_squares(),
+        }
+    }
+    // the pieces of shogi, K R and B move as in chess until R and B are promoted
+    pub fn shogi_squares<R: Ruleset + ?Sized>(&self) -> Squares<R> {
+        match self.kind {
+            // dragon, moves as R or one step diagonally
+            PieceKind::Rook if self.promoted => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, ORTHOGONALS.to_vec(), game, &mut valid, &mut threat);
+                this.leap(pos, DIAGONALS.to_vec(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            // horse, moves as B or one step orthogonally
+            PieceKind::Bishop if self.promoted => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.extend(pos, DIAGONALS.to_vec(), game, &mut valid, &mut threat);
+                this.leap(pos, ORTHOGONALS.to_vec(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            // gold, moves one step in any direction but diagonally backwards
+            // promoted silvers, knights, lances and pawns all move as gold
+            PieceKind::Custom('G' | 'S' | 'L') | PieceKind::Knight | PieceKind::Pawn
+                if self.kind == PieceKind::Custom('G') || self.promoted =>
+            {
+                |pos: &Vector, game: &GameState, _: &R| {
+                    let this = piece_at(game, pos);
+                    let (mut valid, mut threat) = init_masks(game);
+                    let forward = &game.get_player(this.owner).direction;
+                    let offsets = [(0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0), (0, -1)];
+                    this.leap(
+                        pos,
+                        relative(&offsets, forward),
+                        game,
+                        &mut valid,
+                        &mut threat,
+                    );
+                    (valid, threat)
                 }
-                (valid, threatened)
+            }
+            // silver, moves one step diagonally or straight forwards
+            PieceKind::Custom('S') => |pos: &Vector, game: &GameState, _: &R| {
+                piece_at(game, pos).silver_squares(pos, game)
             },
-            'Q' => |pos: &Vector, game: &GameState| {
-                let this = game.get_piece(pos);
-                let (mut valid, mut threat) = init_masks(&game);
-                let directions = vec![
-                    Vector(-1, -1),
-                    Vector(-1, 0),
-                    Vector(-1, 1),
-                    Vector(0, -1),
-                    Vector(0, 1),
-                    Vector(1, -1),
-                    Vector(1, 0),
-                    Vector(1, 1),
-                ];
-                this.extend(pos, directions, &game, &mut valid, &mut threat);
+            // knight, only jumps to the two forward N squares
+            PieceKind::Knight => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                let forward = &game.get_player(this.owner).direction;
+                let offsets = [(1, 2), (-1, 2)];
+                this.leap(
+                    pos,
+                    relative(&offsets, forward),
+                    game,
+                    &mut valid,
+                    &mut threat,
+                );
                 (valid, threat)
             },
-            'R' => |pos: &Vector, game: &GameState| {
-                let this = game.get_piece(pos);
-                let (mut valid, mut threat) = init_masks(&game);
-                let directions = vec![Vector(0, 1), Vector(1, 0), Vector(0, -1), Vector(-1, 0)];
-                this.extend(pos, directions, &game, &mut valid, &mut threat);
+            // lance, moves as R but only forwards
+            PieceKind::Custom('L') => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                let forward = game.get_player(this.owner).direction.clone();
+                this.extend(pos, vec![forward], game, &mut valid, &mut threat);
                 (valid, threat)
             },
-            'B' => |pos: &Vector, game: &GameState| {
-                let this = game.get_piece(pos);
-                let (mut valid, mut threat) = init_masks(&game);
-                let directions = vec![
-                    Vector(1, 1),   // NE
-                    Vector(1, -1),  // SE
-                    Vector(-1, -1), // SW
-                    Vector(-1, 1),  // NW
-                ];
-                this.extend(pos, directions, &game, &mut valid, &mut threat);
+            // pawn, moves and captures one step forwards
+            PieceKind::Pawn => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                let forward = game.get_player(this.owner).direction.clone();
+                this.leap(pos, vec![forward], game, &mut valid, &mut threat);
                 (valid, threat)
             },
-            'N' => |pos: &Vector, game: &GameState| {
-                let this = game.get_piece(pos);
-                let (mut valid, mut threat) = init_masks(&game);
-                //counterclockwise from positive x-axis
-                let offsets = [
-                    Vector(2, 1),
-                    Vector(1, 2),
-                    Vector(-1, 2),
-                    Vector(-2, 1),
-                    Vector(-2, -1),
-                    Vector(-1, -2),
-                    Vector(1, -2),
-                    Vector(2, -1),
-                ];
-                for offset in offsets {
-                    let target = pos.clone() + offset;
-                    this.attack(target, &game, &mut valid, Some(&mut threat));
+            _ => self.claim_squares(),
+        }
+    }
+    // the pieces of makruk and sittuyin, K R and N move as in chess
+    pub fn makruk_squares<R: Ruleset + ?Sized>(&self) -> Squares<R> {
+        match self.kind {
+            // khon, moves as the shogi silver
+            PieceKind::Custom('S') => |pos: &Vector, game: &GameState, _: &R| {
+                piece_at(game, pos).silver_squares(pos, game)
+            },
+            // met, moves one step diagonally
+            PieceKind::Custom('M') => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                this.leap(pos, DIAGONALS.to_vec(), game, &mut valid, &mut threat);
+                (valid, threat)
+            },
+            // moves one step forwards and captures one step diagonally forwards, never double moves
+            PieceKind::Pawn => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let forward = game.get_player(this.owner).direction.clone();
+                let captures = relative(&[(1, 1), (-1, 1)], &forward);
+                this.pawn_squares(pos, game, vec![forward], false, captures)
+            },
+            _ => self.claim_squares(),
+        }
+    }
+    // the pieces of xiangqi, R moves as in chess
+    pub fn xiangqi_squares<R: Ruleset + ?Sized>(&self) -> Squares<R> {
+        match self.kind {
+            // general, moves one step orthogonally inside the palace
+            PieceKind::King => |pos: &Vector, game: &GameState, rules: &R| {
+                let this = piece_at(game, pos);
+                this.royal_squares(pos, game, rules, Piece::general_squares)
+            },
+            // advisor, moves one step diagonally inside the palace
+            PieceKind::Custom('A') => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                for direction in DIAGONALS {
+                    let target = pos.clone() + direction;
+                    if game.in_palace(this.owner, &target) {
+                        this.attack(target, game, &mut valid, Some(&mut threat));
+                    }
                 }
                 (valid, threat)
             },
-            // pawn logic is fundamentally horrific
-            'P' => |pos: &Vector, game: &GameState| {
-                let this = game.get_piece(pos);
-                let (mut valid, mut threat) = init_masks(&game);
-                let unit_vec = game.get_player(this.owner).direction.clone();
-                // initial double move logic
-                let near = pos.clone() + unit_vec.clone();
-                let far = pos.clone() + unit_vec.clone() * 2;
-                let near_is_empty = game.in_bounds(&near) && game.get_piece(&near).id == ' ';
-                let far_is_empty = game.in_bounds(&far) && game.get_piece(&far).id == ' ';
-                if near_is_empty {
-                    this.attack(near, &game, &mut valid, None);
-                    if !this.has_moved && far_is_empty {
-                        let far = pos.clone() + unit_vec.clone() * 2;
-                        this.attack(far, &game, &mut valid, None);
+            // elephant, moves exactly two steps diagonally unless the point between is taken
+            // and cannot cross the river
+            PieceKind::Bishop => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                let (rows, _) = game.board.shape();
+                for direction in DIAGONALS {
+                    let eye = pos.clone() + direction.clone();
+                    let target = pos.clone() + direction * 2;
+                    let own_side = game.relative_rank(this.owner, &target) < rows as i32 / 2;
+                    if game.in_bounds(&target) && own_side && game.get_piece(&eye).is_none() {
+                        this.attack(target, game, &mut valid, Some(&mut threat));
                     }
                 }
-                // diagonal capture logic
-                // 90 deg rotation matrix, useful misnomer, only correct if unit_vec is down
-                let right = Vector(-1 * unit_vec.1, unit_vec.0);
-                let left = right.clone() * -1;
-                let diag_right = pos.clone() + unit_vec.clone() + right.clone();
-                let diag_left = pos.clone() + unit_vec.clone() + left.clone();
-                let can_diagonal = |pos: Vector| {
-                    if !game.board.in_bounds(&pos) {
-                        return false;
-                    };
-                    let diag_piece = game.get_piece(&pos);
-                    diag_piece.id != ' ' && diag_piece.owner != this.owner
-                };
-                let can_right = can_diagonal(diag_right.clone());
-                let can_left = can_diagonal(diag_left.clone());
-                // en passant logic
-                let can_passant = |pos: Vector| {
-                    if !game.board.in_bounds(&pos) {
-                        return false;
+                (valid, threat)
+            },
+            // horse, moves one step orthogonally then one diagonally outwards,
+            // unless the first step is taken
+            PieceKind::Knight => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                for direction in ORTHOGONALS {
+                    let leg = pos.clone() + direction.clone();
+                    if !game.in_bounds(&leg) || game.get_piece(&leg).is_some() {
+                        continue;
                     }
-                    let passant_piece = game.get_piece(&pos);
-                    // if guard b/c get_player will cause underflow
-                    if passant_piece.id == ' ' {
-                        return false;
+                    let side = Vector(-direction.1, direction.0);
+                    let target = leg + direction;
+                    this.attack(
+                        target.clone() + side.clone(),
+                        game,
+                        &mut valid,
+                        Some(&mut threat),
+                    );
+                    this.attack(target + side * -1, game, &mut valid, Some(&mut threat));
+                }
+                (valid, threat)
+            },
+            // cannon, moves as R but only captures by jumping over exactly one piece
+            PieceKind::Custom('C') => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                for direction in ORTHOGONALS {
+                    let mut target = pos.clone() + direction.clone();
+                    while game.in_bounds(&target) && game.get_piece(&target).is_none() {
+                        valid[&target] = true;
+                        target += direction.clone();
                     }
-                    let passant_victim = game.get_player(passant_piece.owner);
-                    if passant_victim.recent_move.is_none() {
-                        return false;
+                    // target is now the screen, everything up to the next piece is attacked
+                    target += direction.clone();
+                    while game.in_bounds(&target) {
+                        threat[&target] = true;
+                        if let Some(piece) = game.get_piece(&target) {
+                            valid[&target] = piece.owner != this.owner && !piece.is_duck();
+                            break;
+                        }
+                        target += direction.clone();
                     }
-                    let recent_move = passant_victim.recent_move.clone().unwrap();
-                    passant_piece.id == 'P'
-                        && passant_piece.owner != this.owner
-                        && recent_move.square_dist() == 4
-                        && recent_move.end == pos
-                };
-                let right_passant = can_passant(pos.clone() + right.clone());
-                let left_passant = can_passant(pos.clone() + left.clone());
-                // need to always threaten diag squares to communicate that enemy K cannot move there
-                if game.board.in_bounds(&diag_right) {
-                    threat[&diag_right] = true;
-                }
-                if game.board.in_bounds(&diag_left) {
-                    threat[&diag_left] = true;
-                }
-                // carry out attacks, needs local variables logic *then* attack due to mut borrow rules
-                if can_right || right_passant {
-                    this.attack(diag_right, &game, &mut valid, Some(&mut threat));
-                }
-                if can_left || left_passant {
-                    this.attack(diag_left, &game, &mut valid, Some(&mut threat));
                 }
                 (valid, threat)
             },
-            _ => |_pos: &Vector, game: &GameState| {
-                let (valid, threat) = init_masks(&game);
+            // soldier, moves one step forwards, and also sideways once across the river
+            PieceKind::Pawn => |pos: &Vector, game: &GameState, _: &R| {
+                let this = piece_at(game, pos);
+                let (mut valid, mut threat) = init_masks(game);
+                let (rows, _) = game.board.shape();
+                let unit_vec = game.get_player(this.owner).direction.clone();
+                let forward = pos.clone() + unit_vec.clone();
+                this.attack(forward, game, &mut valid, Some(&mut threat));
+                if game.relative_rank(this.owner, pos) >= rows as i32 / 2 {
+                    let right = Vector(-unit_vec.1, unit_vec.0);
+                    let left = right.clone() * -1;
+                    this.attack(pos.clone() + right, game, &mut valid, Some(&mut threat));
+                    this.attack(pos.clone() + left, game, &mut valid, Some(&mut threat));
+                }
                 (valid, threat)
             },
+            _ => self.claim_squares(),
         }
     }
+    /*
+     * a K's moves and the squares it is threatened by, see claim_squares
+     * an 'atomic' K can't capture, so it never threatens anything and may stand next to an enemy K
+     */
+    pub fn king_squares<R: Ruleset + ?Sized>(
+        &self,
+        pos: &Vector,
+        game: &GameState,
+        rules: &R,
+        atomic: bool,
+    ) -> (Matrix<bool>, Matrix<bool>) {
+        let (mut valid, mut threat) = init_masks(game);
+        let offsets = vec![
+            Vector(-1, -1),
+            Vector(-1, 0),
+            Vector(-1, 1),
+            Vector(0, -1),
+            Vector(0, 1),
+            Vector(1, -1),
+            Vector(1, 0),
+            Vector(1, 1),
+        ];
+        // check whether a square is threatened by other players
+        let mut threatened = threat.clone(); // accumulated threat matrix of all pieces
+                                             // a K that isn't royal doesn't care about being threatened
+        let royal = game.variant.royal_king();
+        let mut or_assign = |pos: &Vector| {
+            // to prevent infinite recursion
+            let attacker = piece_at(game, pos);
+            // an atomic K can't capture, so it never threatens anything
+            if attacker.kind == PieceKind::King && atomic {
+                return;
+            }
+            if attacker.kind == PieceKind::King {
+                for offset in &offsets {
+                    let target = pos.clone() + offset.clone();
+                    if game.board.in_bounds(&target) {
+                        threatened[&target] = true;
+                    }
+                }
+                return;
+            }
+            // handle non-king pieces
+            let (_, threat) = rules.claim_squares(game, pos);
+            for row in 0..threat.0.len() as i32 {
+                for col in 0..threat.0[0].len() as i32 {
+                    let pos = Vector(col, row);
+                    threatened[&pos] = threatened[&pos] || threat[&pos];
+                }
+            }
+        };
+        for row in 0..game.board.0 .0.len() as i32 {
+            for col in 0..game.board.0 .0[0].len() as i32 {
+                let attacker = game.get_piece(&Vector(col, row));
+                let opposed = matches!(attacker, Some(attacker) if attacker.owner != self.owner);
+                if royal && opposed {
+                    or_assign(&Vector(col, row));
+                }
+            }
+        }
+        // mark standard moves
+        for offset in offsets {
+            let target = pos.clone() + offset;
+            if !game.board.in_bounds(&target) {
+                continue;
+            }
+            // a K touching the enemy K can't be captured, the explosion would take both
+            let safe =
+                !threatened[&target] || (atomic && game.touches_enemy_king(self.owner, &target));
+            let is_capture = game.get_piece(&target).is_some();
+            if safe && !(atomic && is_capture) {
+                self.attack(target, game, &mut valid, Some(&mut threat));
+            }
+        }
+        // mark castles
+        let unit_vec = game.get_player(self.owner).direction.clone();
+        let can_castle = |direction: &Vector| {
+            // def of castle here
+            if game.in_bounds(&(pos.clone() + unit_vec.clone() * -1)) || self.has_moved {
+                return false;
+            }
+            let friendly_rook =
+                |piece: &Piece| game.castling.contains(&piece.kind) && piece.owner == self.owner;
+            let mut target = pos.clone();
+            loop {
+                if threatened[&target] {
+                    return false;
+                }
+                match game.get_piece(&target) {
+                    Some(piece) if friendly_rook(piece) => return !piece.has_moved,
+                    Some(_) if target != *pos => return false,
+                    _ => (),
+                }
+                target += direction.clone();
+                if !game.in_bounds(&target) {
+                    return false;
+                }
+            }
+        };
+        let right = Vector(-unit_vec.1, unit_vec.0);
+        let left = right.clone() * -1;
+        if let Some(distance) = game.variant.castle_distance() {
+            if can_castle(&right) {
+                let target = pos.clone() + right * distance;
+                self.attack(target, game, &mut valid, Some(&mut threat));
+            }
+            if can_castle(&left) {
+                let target = pos.clone() + left * distance;
+                self.attack(target, game, &mut valid, Some(&mut threat));
+            }
+        }
+        (valid, threatened)
+    }
+    // a shogi silver's or makruk khon's moves and threats, one step diagonally or straight forwards
+    fn silver_squares(&self, pos: &Vector, game: &GameState) -> (Matrix<bool>, Matrix<bool>) {
+        let (mut valid, mut threat) = init_masks(game);
+        let forward = &game.get_player(self.owner).direction;
+        let offsets = [(0, 1), (1, 1), (-1, 1), (1, -1), (-1, -1)];
+        self.leap(
+            pos,
+            relative(&offsets, forward),
+            game,
+            &mut valid,
+            &mut threat,
+        );
+        (valid, threat)
+    }
     // functions must be called as move is being made,
     // before pieces are swapped, after player's recent_move field has been updated
-    pub fn side_effects(&self) -> impl FnMut(&Vector, &mut GameState) {
-        match self.id {
+    pub fn side_effects<R: Ruleset + ?Sized>(&self) -> Effects<R> {
+        match self.kind {
             // swap R position
-            'K' => |pos: &Vector, game: &mut GameState| {
-                let player = game.get_player(game.get_piece(pos).owner);
+            PieceKind::King => |pos: &Vector, game: &mut GameState, _: &R| {
+                let player = game.get_player(piece_at(game, pos).owner);
                 let recent_move = player.recent_move.clone().unwrap();
                 let start = recent_move.start.clone();
                 let end = recent_move.end.clone();
                 let displacement = end + start.clone() * -1;
-                let is_castle = match displacement {
-                    Vector(2, 0) | Vector(-2, 0) | Vector(0, 2) | Vector(0, -2) => true,
-                    _ => false,
+                // castling moves the K sideways by the variant's castle distance
+                let distance = match game.variant.castle_distance() {
+                    Some(distance) => distance,
+                    None => return,
                 };
-                let direction = Vector(displacement.0 / 2, displacement.1 / 2);
+                let is_castle = player.direction.0 * displacement.0 == 0
+                    && player.direction.1 * displacement.1 == 0
+                    && displacement.0.abs().max(displacement.1.abs()) == distance;
+                let direction = Vector(displacement.0 / distance, displacement.1 / distance);
                 let find_rook = || {
                     let mut target = start.clone();
-                    while game.get_piece(&target).id != 'R' {
+                    let is_rook = |piece: &Piece| game.castling.contains(&piece.kind);
+                    while !game.get_piece(&target).is_some_and(is_rook) {
                         target += direction.clone();
                     }
                     target
                 };
                 if is_castle {
                     let rook_pos = find_rook();
-                    game.board[&(start + direction)] = game.board[&rook_pos].clone();
-                    game.board[&rook_pos] = Piece::new();
+                    game.board[&(start + direction * (distance - 1))] =
+                        game.board[&rook_pos].take();
                 }
             },
             // destroy en passant'ed pawn
-            'P' => |pos: &Vector, game: &mut GameState| {
-                let player = game.get_player(game.get_piece(pos).owner);
+            PieceKind::Pawn => |pos: &Vector, game: &mut GameState, rules: &R| {
+                let player = game.get_player(piece_at(game, pos).owner);
                 let recent_move = player.recent_move.clone().unwrap();
                 let end_pos = recent_move.end.clone();
-                let unit_vec = player.direction.clone();
+                let promotion = player.promotion;
                 // recent_move guaranteed to be Some
-                let is_capture = game.get_piece(&end_pos).id != ' ';
-                if !is_capture && recent_move.is_diag() {
-                    game.board[&(end_pos.clone() + unit_vec.clone() * -1)] = Self::new();
+                if let Some(victim) = game.passant_victim(&recent_move.start, &end_pos) {
+                    rules.capture(game, &victim);
                 }
-                // promotion
-                if !game.in_bounds(&(end_pos + unit_vec)) {
-                    game.board[&recent_move.start] = Piece {
-                        id: 'Q', // TODO: ask for user input
+                // promotion, to the player's choice if it's allowed here, else staying a P if that is
+                let owner = recent_move.piece.owner;
+                let choices = match rules.promotes_on(game, owner, &end_pos) {
+                    true => rules.promotion_choices(game, owner, &end_pos),
+                    false => vec![None],
+                };
+                let promotion = match choices.contains(&promotion) {
+                    true => promotion,
+                    false if choices.contains(&None) => None,
+                    false => choices.first().copied().flatten(),
+                };
+                if let Some(kind) = promotion {
+                    game.board[&recent_move.start] = Some(Piece {
+                        kind,
                         owner: recent_move.piece.owner,
                         has_moved: true,
-                    };
+                        promoted: true,
+                    });
                 }
             },
-            _ => |_pos: &Vector, _game: &mut GameState| {},
+            _ => |_: &Vector, _: &mut GameState, _: &R| {},
+        }
+    }
+    // the same for shogi, where pieces promote instead of castling or taking en passant
+    pub fn shogi_side_effects<R: Ruleset + ?Sized>(&self) -> Effects<R> {
+        match self.kind {
+            // promote when moving into, out of, or within the promotion zone, if the player chose to
+            PieceKind::Rook
+            | PieceKind::Bishop
+            | PieceKind::Custom('S' | 'L')
+            | PieceKind::Knight
+            | PieceKind::Pawn => {
+                |pos: &Vector, game: &mut GameState, rules: &R| {
+                    let this = piece_at(game, pos);
+                    let player = game.get_player(this.owner);
+                    let recent_move = player.recent_move.clone().unwrap();
+                    let unit_vec = player.direction.clone();
+                    let in_zone = |pos: &Vector| rules.promotes_on(game, this.owner, pos);
+                    // a piece which could never move again has to promote
+                    let reach = match this.kind {
+                        PieceKind::Pawn | PieceKind::Custom('L') => 1,
+                        PieceKind::Knight => 2,
+                        _ => 0,
+                    };
+                    let stuck = reach > 0
+                        && !game.in_bounds(&(recent_move.end.clone() + unit_vec.clone() * reach));
+                    let can_promote = !this.promoted
+                        && (in_zone(&recent_move.start) || in_zone(&recent_move.end));
+                    if can_promote && (player.promotion.is_some() || stuck) {
+                        game.get_piece_mut(pos).promoted = true;
+                    }
+                }
+            }
+            _ => self.side_effects(),
         }
     }
     // called from each piece to attack a position, determines whether it is possible
@@ -320,26 +797,31 @@ impl Piece {
             //guard against out of bounds
             return false;
         };
-        let is_occupied = state.board[&pos].id != ' ';
-        let is_opposed = state.board[&pos].owner != self.owner;
+        let target = state.get_piece(&pos);
+        // nothing can pass through or land on the duck
+        if target.is_some_and(Piece::is_duck) {
+            return false;
+        }
+        let is_occupied = target.is_some();
+        let is_opposed = target.is_some_and(|piece| piece.owner != self.owner);
         match (is_occupied, is_opposed) {
             (false, _) => {
                 valid[&pos] = true;
-                if threat.is_some() {
-                    threat.unwrap()[&pos] = true;
+                if let Some(threat) = threat {
+                    threat[&pos] = true;
                 }
                 true
             }
             (true, true) => {
                 valid[&pos] = true;
-                if threat.is_some() {
-                    threat.unwrap()[&pos] = true;
+                if let Some(threat) = threat {
+                    threat[&pos] = true;
                 }
                 false
             }
             (true, false) => {
-                if threat.is_some() {
-                    threat.unwrap()[&pos] = true;
+                if let Some(threat) = threat {
+                    threat[&pos] = true;
                 }
                 false
             }
@@ -363,30 +845,183 @@ impl Piece {
             }
         }
     }
+    /*
+     * K claim_squares for boards with their own geometry, 'steps' gives a K's moves and threats
+     * returns the K's safe moves, and the squares threatened by others like any other K
+     */
+    fn royal_squares<R: Ruleset + ?Sized>(
+        &self,
+        pos: &Vector,
+        game: &GameState,
+        rules: &R,
+        steps: Steps,
+    ) -> (Matrix<bool>, Matrix<bool>) {
+        let (_, mut threatened) = init_masks(game);
+        let (rows, cols) = game.board.shape();
+        for row in 0..rows as i32 {
+            for col in 0..cols as i32 {
+                let attacker = match game.get_piece(&Vector(col, row)) {
+                    Some(attacker) if attacker.owner != PlayerId::NEUTRAL => attacker,
+                    _ => continue,
+                };
+                if attacker.owner == self.owner {
+                    continue;
+                }
+                // an enemy K only counts its own steps, to prevent infinite recursion
+                let (_, threat) = match attacker.kind {
+                    PieceKind::King => steps(attacker, &Vector(col, row), game),
+                    _ => rules.claim_squares(game, &Vector(col, row)),
+                };
+                for row in 0..rows as i32 {
+                    for col in 0..cols as i32 {
+                        let pos = Vector(col, row);
+                        threatened[&pos] = threatened[&pos] || threat[&pos];
+                    }
+                }
+            }
+        }
+        let (mut valid, _) = steps(self, pos, game);
+        for row in 0..rows as i32 {
+            for col in 0..cols as i32 {
+                let pos = Vector(col, row);
+                valid[&pos] = valid[&pos] && !threatened[&pos];
+            }
+        }
+        (valid, threatened)
+    }
+    // a hexagonal K's moves and threats, one step to any of the 12 nearest cells
+    fn hex_king_squares(&self, pos: &Vector, game: &GameState) -> (Matrix<bool>, Matrix<bool>) {
+        let (mut valid, mut threat) = init_masks(game);
+        self.leap(
+            pos,
+            hex::ORTHOGONALS.to_vec(),
+            game,
+            &mut valid,
+            &mut threat,
+        );
+        self.leap(pos, hex::DIAGONALS.to_vec(), game, &mut valid, &mut threat);
+        (valid, threat)
+    }
+    // a raumschach K's moves and threats, one step to any of the 26 neighbouring cells
+    fn cube_king_squares(&self, pos: &Vector, game: &GameState) -> (Matrix<bool>, Matrix<bool>) {
+        let (mut valid, mut threat) = init_masks(game);
+        self.leap(pos, cube::orthogonals(), game, &mut valid, &mut threat);
+        self.leap(pos, cube::diagonals(), game, &mut valid, &mut threat);
+        self.leap(pos, cube::triagonals(), game, &mut valid, &mut threat);
+        (valid, threat)
+    }
+    /*
+     * the xiangqi general's own moves (return_tuple.0) and threats (return_tuple.1)
+     * it attacks orthogonally inside its palace, and along its file up to the first piece,
+     * which is how generals facing each other are ruled out
+     */
+    fn general_squares(&self, pos: &Vector, game: &GameState) -> (Matrix<bool>, Matrix<bool>) {
+        let (mut valid, mut threat) = init_masks(game);
+        for direction in ORTHOGONALS {
+            let target = pos.clone() + direction;
+            if game.in_palace(self.owner, &target) {
+                self.attack(target, game, &mut valid, Some(&mut threat));
+            }
+        }
+        let forward = game.get_player(self.owner).direction.clone();
+        let mut target = pos.clone() + forward.clone();
+        while game.in_bounds(&target) {
+            threat[&target] = true;
+            if game.get_piece(&target).is_some() {
+                break;
+            }
+            target += forward.clone();
+        }
+        (valid, threat)
+    }
+    /*
+     * a P's moves (return_tuple.0) and threats (return_tuple.1), common to every board
+     * it steps along each of 'moves' onto an empty square, a second time if 'double', and
+     * captures onto each of 'captures', en passant included
+     */
+    fn pawn_squares(
+        &self,
+        pos: &Vector,
+        game: &GameState,
+        moves: Vec<Vector>,
+        double: bool,
+        captures: Vec<Vector>,
+    ) -> (Matrix<bool>, Matrix<bool>) {
+        let (mut valid, mut threat) = init_masks(game);
+        for step in moves {
+            let is_empty = |pos: &Vector| game.in_bounds(pos) && game.get_piece(pos).is_none();
+            let near = pos.clone() + step.clone();
+            let far = near.clone() + step;
+            if is_empty(&near) {
+                self.attack(near, game, &mut valid, None);
+                if double && is_empty(&far) {
+                    self.attack(far, game, &mut valid, None);
+                }
+            }
+        }
+        for offset in captures {
+            let target = pos.clone() + offset;
+            if !game.in_bounds(&target) {
+                continue;
+            }
+            // need to always threaten capture squares so the enemy K cannot move there
+            threat[&target] = true;
+            let can_capture =
+                matches!(game.get_piece(&target), Some(piece) if piece.owner != self.owner);
+            if can_capture || game.passant_victim(pos, &target).is_some() {
+                self.attack(target, game, &mut valid, Some(&mut threat));
+            }
+        }
+        (valid, threat)
+    }
+    // attack each offset once, jumping over anything in between, common to N A and C
+    fn leap(
+        &self,
+        pos: &Vector,
+        offsets: Vec<Vector>,
+        state: &GameState,
+        valid: &mut Matrix<bool>,
+        threat: &mut Matrix<bool>,
+    ) {
+        for offset in offsets {
+            self.attack(pos.clone() + offset, state, valid, Some(threat));
+        }
+    }
     /*
      * speculates what would happen if self made each move in valid
-     * unmarks as valid and threat if such a move would threaten friendly K
+     * unmarks as valid and threat if the rules don't let its owner leave the position reached,
+     * see Ruleset::may_leave
      */
-    pub fn speculate(
+    pub fn speculate<R: Ruleset + ?Sized>(
         &self,
         mut valid: Matrix<bool>,
         mut threat: Matrix<bool>,
         start: &Vector,
         game: &GameState,
+        rules: &R,
     ) -> (Matrix<bool>, Matrix<bool>) {
+        if !game.variant.royal_king() {
+            return (valid, threat);
+        }
         let (rows, cols) = valid.shape();
         for row in 0..rows as i32 {
             for col in 0..cols as i32 {
                 let end = Vector(col, row);
                 if valid[&end] {
                     let mut speculation = game.clone();
-                    // speculation.board[start].side_effects()(start, &mut speculation);
-                    speculation.board[&end] = speculation.board[start].clone();
-                    speculation.board[start] = Self::new();
-                    let king_pos = &speculation.get_player(self.owner).king_pos;
-                    let (_, threatened) =
-                        speculation.board[&king_pos].claim_squares()(king_pos, &speculation);
-                    if threatened[king_pos] {
+                    let captures = speculation.captured.len();
+                    if let Some(victim) = game.passant_victim(start, &end) {
+                        rules.capture(&mut speculation, &victim);
+                    }
+                    rules.capture(&mut speculation, &end);
+                    speculation.board[&end] = speculation.board[start].take();
+                    if Some(self.kind) == game.get_player(self.owner).royal {
+                        speculation.move_king(self.owner, end.clone());
+                    }
+                    if speculation.captured.len() > captures {
+                        rules.capture_effects(&mut speculation, &end);
+                    }
+                    if !rules.may_leave(&speculation, self.owner) {
                         valid[&end] = false;
                         threat[&end] = false;
                     }
@@ -0,0 +1,557 @@
+use crate::board::{Matrix, Vector};
+use crate::cube;
+use crate::game::{Count, GameState, State};
+use crate::outcome::Outcome;
+use crate::piece::{Piece, PieceKind, PlayerId, Squares};
+use crate::setup::Setup;
+use crate::variant::Variant;
+
+/*
+ * The rules a Game is played by, chosen when the game is created, e.g.
+ *
+ *     Game::with_rules(ruleset::from_variant(Variant::Atomic))
+ *
+ * a ruleset only has to give its starting position, the rest defaults to the rules of standard
+ * chess: the pieces moving as in Piece::claim_squares, each player keeping their royal piece out
+ * of check, drops from hand, and the game ending by the win conditions of the variant its
+ * position is played in
+ * variants which play differently override the parts that differ, see built_in
+ */
+pub trait Ruleset {
+    // the position the game starts from, Game works out each player's state by these rules
+    fn start(&self) -> GameState;
+    /*
+     * squares the piece at pos could move to (return_tuple.0) and threatens (return_tuple.1),
+     * before ruling out moves which leave its own royal piece threatened
+     * as in Piece::claim_squares, a K gives the squares it is threatened by instead
+     */
+    fn claim_squares(&self, state: &GameState, pos: &Vector) -> (Matrix<bool>, Matrix<bool>) {
+        claim(self, state, pos, Piece::claim_squares)
+    }
+    // moves the piece at 'from' to 'to', Game then ends the turn
+    fn apply_move(&self, state: &mut GameState, from: &Vector, to: &Vector) {
+        state.apply_move(self, from, to);
+    }
+    // what the piece at pos does as it moves, before it leaves pos, e.g. castling or en passant
+    fn side_effects(&self, state: &mut GameState, pos: &Vector) {
+        if let Some(piece) = state.get_piece(pos) {
+            piece.side_effects()(pos, state, self);
+        }
+    }
+    // takes the piece at pos off the board
+    fn capture(&self, state: &mut GameState, pos: &Vector) {
+        state.capture(pos);
+    }
+    // what a capture does once the capturing piece has landed on pos
+    fn capture_effects(&self, _: &mut GameState, _: &Vector) {}
+    // the squares the piece at pos may legally move to
+    fn legal_moves(&self, state: &GameState, pos: &Vector) -> Matrix<bool> {
+        state.legal_moves(self, pos)
+    }
+    // whether any piece of a player other than player_id threatens pos
+    fn attacked(&self, state: &GameState, player_id: PlayerId, pos: &Vector) -> bool {
+        state.attacked(self, player_id, pos)
+    }
+    // whether player_id's royal piece is threatened
+    fn king_threatened(&self, state: &GameState, player_id: PlayerId) -> bool {
+        state.king_threatened(self, player_id)
+    }
+    // whether player_id may leave the position their move has reached, see GameState::king_safe
+    fn may_leave(&self, state: &GameState, player_id: PlayerId) -> bool {
+        state.king_safe(self, player_id)
+    }
+    // whether a P of player_id's promotes on reaching pos, on their promotion ranks by default
+    fn promotes_on(&self, state: &GameState, player_id: PlayerId, pos: &Vector) -> bool {
+        let player = state.get_player(player_id);
+        let line = match player.direction {
+            Vector(_, 0) => pos.0,
+            _ => pos.1,
+        };
+        player.promotion_ranks.contains(&line)
+    }
+    // what a P of player_id's may become on reaching pos, see Variant::promotion_pieces
+    fn promotion_choices(
+        &self,
+        state: &GameState,
+        _: PlayerId,
+        _: &Vector,
+    ) -> Vec<Option<PieceKind>> {
+        state.variant.promotion_pieces().to_vec()
+    }
+    // whether player_id is in check, and whether they have any legal moves
+    fn update_check(&self, state: &GameState, player_id: PlayerId) -> State {
+        state.update_check(self, player_id)
+    }
+    // whether kind may be dropped on the empty square pos at all, before the K's safety is checked
+    fn may_drop(&self, state: &GameState, kind: PieceKind, _: PlayerId, pos: &Vector) -> bool {
+        !pawn_on_back_rank(state, kind, pos)
+    }
+    // the squares player_id may drop a piece of 'kind' from their hand on
+    fn drop_squares(
+        &self,
+        state: &GameState,
+        kind: PieceKind,
+        player_id: PlayerId,
+    ) -> Matrix<bool> {
+        state.drop_squares(self, kind, player_id)
+    }
+    // remembers the position just reached, for repetition rules such as sennichite
+    fn record_position(&self, state: &mut GameState) {
+        state.record_position();
+    }
+    // starts, restarts or stops counting rules such as makruk's once a move has been made
+    fn update_count(&self, _: &mut GameState) {}
+    // how the game has ended, if it has, called once each move has been made
+    fn outcome(&self, state: &GameState) -> Option<Outcome> {
+        let conditions = state.variant.win_conditions();
+        conditions
+            .iter()
+            .find_map(|condition| condition.evaluate(state, self))
+    }
+}
+
+// the squares the piece at pos claims by 'table', nothing for an empty square
+fn claim<R: Ruleset + ?Sized>(
+    rules: &R,
+    state: &GameState,
+    pos: &Vector,
+    table: fn(&Piece) -> Squares<R>,
+) -> (Matrix<bool>, Matrix<bool>) {
+    match state.get_piece(pos) {
+        Some(piece) => table(piece)(pos, state, rules),
+        None => {
+            let (rows, cols) = state.board.shape();
+            (
+                Matrix::new(false, rows, cols),
+                Matrix::new(false, rows, cols),
+            )
+        }
+    }
+}
+
+// P can't be dropped on the first or last rank
+fn pawn_on_back_rank(state: &GameState, kind: PieceKind, pos: &Vector) -> bool {
+    let (rows, _) = state.board.shape();
+    kind == PieceKind::Pawn && (pos.1 == 0 || pos.1 as usize == rows - 1)
+}
+
+// the built-in rules of the variant, from its starting position
+pub fn from_variant(variant: Variant) -> Box<dyn Ruleset> {
+    let start = GameState::from_fen(variant.start_fen(), variant)
+        .expect("starting positions are valid FEN");
+    built_in(start)
+}
+
+pub fn from_setup(setup: &Setup) -> Box<dyn Ruleset> {
+    built_in(GameState::from_setup(setup))
+}
+
+// the built-in rules of the variant 'start' is played in, starting from it, e.g. one read from FEN
+pub fn built_in(start: GameState) -> Box<dyn Ruleset> {
+    match start.variant {
+        Variant::Grand => Box::new(Grand { start }),
+        Variant::Crazyhouse => Box::new(Crazyhouse { start }),
+        Variant::Atomic => Box::new(Atomic { start }),
+        Variant::RacingKings => Box::new(RacingKings { start }),
+        Variant::Xiangqi => Box::new(Xiangqi { start }),
+        Variant::Shogi => Box::new(Shogi { start }),
+        Variant::Hexagonal => Box::new(Hexagonal { start }),
+        Variant::Raumschach => Box::new(Raumschach { start }),
+        Variant::Makruk => Box::new(Makruk { start }),
+        Variant::Sittuyin => Box::new(Sittuyin { start }),
+        _ => Box::new(StandardChess::from_state(start)),
+    }
+}
+
+// standard chess, and the variants which only differ from it in their position or win conditions
+pub struct StandardChess {
+    start: GameState,
+}
+
+impl StandardChess {
+    // starts from any position, e.g. one read from FEN
+    pub fn from_state(start: GameState) -> StandardChess {
+        StandardChess { start }
+    }
+}
+
+impl Default for StandardChess {
+    fn default() -> Self {
+        let variant = Variant::Standard;
+        let start = GameState::from_fen(variant.start_fen(), variant)
+            .expect("starting positions are valid FEN");
+        Self::from_state(start)
+    }
+}
+
+impl Ruleset for StandardChess {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+}
+
+// P only promote to a kind their owner has lost, and may stay P short of the last rank
+struct Grand {
+    start: GameState,
+}
+
+impl Ruleset for Grand {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+    fn promotion_choices(
+        &self,
+        state: &GameState,
+        player_id: PlayerId,
+        pos: &Vector,
+    ) -> Vec<Option<PieceKind>> {
+        let last_rank =
+            !state.in_bounds(&(pos.clone() + state.get_player(player_id).direction.clone()));
+        let placement = state
+            .variant
+            .start_fen()
+            .split(' ')
+            .next()
+            .unwrap_or_default();
+        let has_lost = |kind: PieceKind| {
+            let is_kind = |id: &char| PieceKind::from_char(id.to_ascii_uppercase()) == kind;
+            let ours = |id: &char| id.is_ascii_uppercase() == (player_id == PlayerId(1));
+            let start = placement
+                .chars()
+                .filter(|id| id.is_ascii_alphabetic() && ours(id) && is_kind(id));
+            let on_board = state.board.0 .0.iter().flatten().flatten();
+            on_board
+                .filter(|piece| piece.owner == player_id && piece.kind == kind)
+                .count()
+                < start.count()
+        };
+        let pieces = state.variant.promotion_pieces().iter().copied();
+        let mut choices: Vec<_> = pieces.filter(|piece| piece.is_some_and(has_lost)).collect();
+        if !last_rank {
+            choices.push(None);
+        }
+        choices
+    }
+}
+
+// the player to move takes what they capture in hand, promoted pieces go back to being P
+struct Crazyhouse {
+    start: GameState,
+}
+
+impl Ruleset for Crazyhouse {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+    fn capture(&self, state: &mut GameState, pos: &Vector) {
+        if let Some(piece) = state.get_piece(pos) {
+            let kind = match piece.promoted {
+                true => PieceKind::Pawn,
+                false => piece.kind,
+            };
+            state.add_to_hand(state.turn(), kind);
+        }
+        state.capture(pos);
+    }
+}
+
+// captures explode, K can't capture and touching K can't check each other
+struct Atomic {
+    start: GameState,
+}
+
+impl Ruleset for Atomic {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+    fn claim_squares(&self, state: &GameState, pos: &Vector) -> (Matrix<bool>, Matrix<bool>) {
+        match state.get_piece(pos) {
+            Some(king) if king.kind == PieceKind::King => king.king_squares(pos, state, self, true),
+            _ => claim(self, state, pos, Piece::claim_squares),
+        }
+    }
+    // explode the capturing piece, and every piece but P around the capture
+    fn capture_effects(&self, state: &mut GameState, pos: &Vector) {
+        self.capture(state, pos);
+        for offset in [
+            Vector(-1, -1),
+            Vector(-1, 0),
+            Vector(-1, 1),
+            Vector(0, -1),
+            Vector(0, 1),
+            Vector(1, -1),
+            Vector(1, 0),
+            Vector(1, 1),
+        ] {
+            let target = pos.clone() + offset;
+            let is_pawn = |piece: &Piece| piece.kind == PieceKind::Pawn;
+            if state.in_bounds(&target) && !state.get_piece(&target).is_some_and(is_pawn) {
+                self.capture(state, &target);
+            }
+        }
+    }
+    fn king_threatened(&self, state: &GameState, player_id: PlayerId) -> bool {
+        let touching = match &state.get_player(player_id).king_pos {
+            Some(king_pos) => state.touches_enemy_king(player_id, king_pos),
+            None => false,
+        };
+        !touching && state.king_threatened(self, player_id)
+    }
+}
+
+// giving check isn't allowed either
+struct RacingKings {
+    start: GameState,
+}
+
+impl Ruleset for RacingKings {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+    fn may_leave(&self, state: &GameState, player_id: PlayerId) -> bool {
+        let checks = PlayerId::all(state.num_players())
+            .any(|enemy| enemy != player_id && self.king_threatened(state, enemy));
+        state.king_safe(self, player_id) && !checks
+    }
+}
+
+struct Xiangqi {
+    start: GameState,
+}
+
+impl Ruleset for Xiangqi {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+    fn claim_squares(&self, state: &GameState, pos: &Vector) -> (Matrix<bool>, Matrix<bool>) {
+        claim(self, state, pos, Piece::xiangqi_squares)
+    }
+}
+
+/*
+ * captured pieces go to the captor's hand turned back over, pieces promote by choice in the
+ * promotion zone, and drops are restricted: no piece where it could never move, no P on a file
+ * holding another unpromoted P of its owner's, and no P drop giving checkmate
+ */
+struct Shogi {
+    start: GameState,
+}
+
+impl Ruleset for Shogi {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+    fn claim_squares(&self, state: &GameState, pos: &Vector) -> (Matrix<bool>, Matrix<bool>) {
+        claim(self, state, pos, Piece::shogi_squares)
+    }
+    fn side_effects(&self, state: &mut GameState, pos: &Vector) {
+        if let Some(piece) = state.get_piece(pos) {
+            piece.shogi_side_effects()(pos, state, self);
+        }
+    }
+    fn capture(&self, state: &mut GameState, pos: &Vector) {
+        if let Some(piece) = state.get_piece(pos) {
+            state.add_to_hand(state.turn(), piece.kind);
+        }
+        state.capture(pos);
+    }
+    fn may_drop(
+        &self,
+        state: &GameState,
+        kind: PieceKind,
+        player_id: PlayerId,
+        pos: &Vector,
+    ) -> bool {
+        let (rows, _) = state.board.shape();
+        let rank = state.relative_rank(player_id, pos);
+        let stuck = match kind {
+            PieceKind::Pawn | PieceKind::Custom('L') => rank == rows as i32 - 1,
+            PieceKind::Knight => rank >= rows as i32 - 2,
+            _ => false,
+        };
+        let nifu = kind == PieceKind::Pawn
+            && (0..rows as i32).any(|row| {
+                matches!(state.get_piece(&Vector(pos.0, row)), Some(piece)
+                    if piece.kind == PieceKind::Pawn && piece.owner == player_id && !piece.promoted)
+            });
+        !stuck && !nifu
+    }
+    fn drop_squares(
+        &self,
+        state: &GameState,
+        kind: PieceKind,
+        player_id: PlayerId,
+    ) -> Matrix<bool> {
+        let mut valid = state.drop_squares(self, kind, player_id);
+        if kind != PieceKind::Pawn {
+            return valid;
+        }
+        let (rows, cols) = state.board.shape();
+        for row in 0..rows as i32 {
+            for col in 0..cols as i32 {
+                let pos = Vector(col, row);
+                if !valid[&pos] {
+                    continue;
+                }
+                let mut speculation = state.clone();
+                speculation.board[&pos] = Some(state.dropped_piece(kind, player_id, &pos));
+                valid[&pos] = !PlayerId::all(state.num_players()).any(|enemy| {
+                    // a P check can't be blocked, so the enemy's hand is no help,
+                    // emptying it also stops their own P drops from recursing back here
+                    speculation.empty_hand(enemy);
+                    enemy != player_id
+                        && matches!(self.update_check(&speculation, enemy), State::Checkmate)
+                });
+            }
+        }
+        valid
+    }
+}
+
+// P promote at the end of their file, files on the hexagonal board all end on different ranks
+struct Hexagonal {
+    start: GameState,
+}
+
+impl Ruleset for Hexagonal {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+    fn claim_squares(&self, state: &GameState, pos: &Vector) -> (Matrix<bool>, Matrix<bool>) {
+        claim(self, state, pos, Piece::hex_squares)
+    }
+    fn promotes_on(&self, state: &GameState, player_id: PlayerId, pos: &Vector) -> bool {
+        let direction = state.get_player(player_id).direction.clone();
+        !state.in_bounds(&(pos.clone() + direction))
+    }
+}
+
+// P promote on the far rank of the far level
+struct Raumschach {
+    start: GameState,
+}
+
+impl Ruleset for Raumschach {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+    fn claim_squares(&self, state: &GameState, pos: &Vector) -> (Matrix<bool>, Matrix<bool>) {
+        claim(self, state, pos, Piece::cube_squares)
+    }
+    fn promotes_on(&self, state: &GameState, player_id: PlayerId, pos: &Vector) -> bool {
+        let [forwards, upwards] = cube::pawn_moves(&state.get_player(player_id).direction);
+        !state.in_bounds(&(pos.clone() + forwards)) && !state.in_bounds(&(pos.clone() + upwards))
+    }
+}
+
+// the game is drawn if it drags on once no P are left, see Count
+struct Makruk {
+    start: GameState,
+}
+
+impl Ruleset for Makruk {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+    fn claim_squares(&self, state: &GameState, pos: &Vector) -> (Matrix<bool>, Matrix<bool>) {
+        claim(self, state, pos, Piece::makruk_squares)
+    }
+    fn update_count(&self, state: &mut GameState) {
+        let pieces: Vec<&Piece> = state.board.0 .0.iter().flatten().flatten().collect();
+        if pieces.iter().any(|piece| piece.kind == PieceKind::Pawn) {
+            state.count = None;
+            return;
+        }
+        let owned = |player_id: PlayerId, kind: PieceKind| {
+            let owned = pieces.iter().filter(|piece| piece.owner == player_id);
+            owned.filter(|piece| piece.kind == kind).count()
+        };
+        let bare_king = PlayerId::all(state.num_players()).find(|player_id| {
+            pieces
+                .iter()
+                .all(|p| p.owner != *player_id || p.kind == PieceKind::King)
+        });
+        let moves = match bare_king {
+            Some(_)
+                if matches!(
+                    state.count,
+                    Some(Count {
+                        bare_king: true,
+                        ..
+                    })
+                ) =>
+            {
+                return
+            }
+            // the count starts from the number of pieces left, and ends at a limit set by
+            // the strongest of the other side's pieces
+            Some(bare) => {
+                let strong = bare.next(state.num_players());
+                let (rooks, khons, knights) = (
+                    owned(strong, PieceKind::Rook),
+                    owned(strong, PieceKind::Custom('S')),
+                    owned(strong, PieceKind::Knight),
+                );
+                let limit: u32 = match (rooks, khons, knights) {
+                    (2.., _, _) => 8,
+                    (1, _, _) => 16,
+                    (0, 2.., _) => 22,
+                    (0, 0, 2..) => 32,
+                    (0, 1, _) => 44,
+                    _ => 64,
+                };
+                let counted = pieces
+                    .iter()
+                    .filter(|piece| piece.owner != PlayerId::NEUTRAL)
+                    .count()
+                    + 1;
+                limit.saturating_sub(counted as u32)
+            }
+            None if state.count.is_some() => return,
+            None => 64,
+        };
+        state.count = Some(Count {
+            start: state.halfmove_counter(),
+            moves,
+            bare_king: bare_king.is_some(),
+        });
+    }
+}
+
+/*
+ * played with makruk's pieces, which are placed from hand on the player's first three ranks,
+ * R on the first, and P promote on the long diagonals in the enemy's half while their owner
+ * has no M
+ */
+struct Sittuyin {
+    start: GameState,
+}
+
+impl Ruleset for Sittuyin {
+    fn start(&self) -> GameState {
+        self.start.clone()
+    }
+    fn claim_squares(&self, state: &GameState, pos: &Vector) -> (Matrix<bool>, Matrix<bool>) {
+        claim(self, state, pos, Piece::makruk_squares)
+    }
+    fn promotes_on(&self, state: &GameState, player_id: PlayerId, pos: &Vector) -> bool {
+        let (rows, cols) = state.board.shape();
+        let on_diagonal = pos.0 == pos.1 || pos.0 + pos.1 == cols as i32 - 1;
+        let enemy_half = state.relative_rank(player_id, pos) >= rows as i32 / 2;
+        on_diagonal && enemy_half && !state.has_piece(player_id, PieceKind::Custom('M'))
+    }
+    fn may_drop(
+        &self,
+        state: &GameState,
+        kind: PieceKind,
+        player_id: PlayerId,
+        pos: &Vector,
+    ) -> bool {
+        let rank = state.relative_rank(player_id, pos);
+        let on_side = match kind {
+            PieceKind::Rook => rank == 0,
+            _ => rank <= 2,
+        };
+        on_side && !pawn_on_back_rank(state, kind, pos)
+    }
+}
@@ -0,0 +1,132 @@
+use crate::board::Vector;
+use crate::game::{GameState, State};
+use crate::piece::{Piece, PieceKind};
+use crate::ruleset::Ruleset;
+
+/*
+ * Standard algebraic notation, as used in PGN, e.g. Nbd7, exd5, O-O, e8=Q+ or P@f7#
+ * squares are written in the variant's own notation, so SAN on the hexagonal board reads f11
+ * a move is written in three parts: what moved and where (see move_body), the piece it promoted
+ * to (see promotion), then whether it gives check (see check)
+ */
+
+// the move of the piece at 'from' to 'to' in 'state', before it is made, e.g. Nbd7 or exd5
+pub fn move_body(state: &GameState, rules: &dyn Ruleset, from: &Vector, to: &Vector) -> String {
+    let variant = state.variant;
+    let piece = state.get_piece(from).expect("no piece to move");
+    let square = |pos: &Vector| variant.notation(pos).expect("moves are on the board");
+    let displacement = to.clone() + from.clone() * -1;
+    // castling is written by the side the K goes to, O-O towards the h-file
+    if piece.kind == PieceKind::King {
+        if let Some(distance) = variant.castle_distance() {
+            if displacement.1 == 0 && displacement.0.abs() == distance {
+                return String::from(if displacement.0 > 0 { "O-O" } else { "O-O-O" });
+            }
+        }
+    }
+    let capture = if state.is_capture(from, to) { "x" } else { "" };
+    // P are only named by their file, and only when capturing
+    if piece.kind == PieceKind::Pawn && !piece.promoted {
+        let file = match capture {
+            "x" => file_of(&square(from)),
+            _ => String::new(),
+        };
+        return format!("{}{}{}", file, capture, square(to));
+    }
+    let origin = disambiguation(state, rules, piece, from, to);
+    // a shogi piece which has been turned over is written +S
+    let promoted = match piece.promoted && variant.shows_promotions() {
+        true => "+",
+        false => "",
+    };
+    format!(
+        "{}{}{}{}{}",
+        promoted,
+        piece.kind,
+        origin,
+        capture,
+        square(to)
+    )
+}
+
+/*
+ * as little of the starting square as tells the piece apart from others of its kind which could
+ * also move to 'to': the file if that is enough, then the rank, then the whole square
+ */
+fn disambiguation(
+    state: &GameState,
+    rules: &dyn Ruleset,
+    piece: &Piece,
+    from: &Vector,
+    to: &Vector,
+) -> String {
+    let (rows, cols) = state.board.shape();
+    let mut rivals = Vec::new();
+    for row in 0..rows as i32 {
+        for col in 0..cols as i32 {
+            let pos = Vector(col, row);
+            let rival = match state.get_piece(&pos) {
+                Some(rival) => rival,
+                None => continue,
+            };
+            let alike = rival.kind == piece.kind
+                && rival.owner == piece.owner
+                && rival.promoted == piece.promoted;
+            if pos != *from && alike && rules.legal_moves(state, &pos)[to] {
+                rivals.push(pos);
+            }
+        }
+    }
+    let square = state
+        .variant
+        .notation(from)
+        .expect("moves are on the board");
+    if rivals.is_empty() {
+        String::new()
+    } else if rivals.iter().all(|rival| rival.0 != from.0) {
+        file_of(&square)
+    } else if rivals.iter().all(|rival| rival.1 != from.1) {
+        square[file_of(&square).len()..].to_string()
+    } else {
+        square
+    }
+}
+
+// the letters a square's notation starts with, e.g. e from e4, or Cc from Cc3 in raumschach
+fn file_of(square: &str) -> String {
+    square
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .collect()
+}
+
+/*
+ * what the moved piece became, from the piece before the move and the one on its end square after
+ * e.g. =Q, or =+ for a shogi piece turned over
+ */
+pub fn promotion(before: &Piece, after: Option<&Piece>) -> String {
+    match after {
+        Some(after) if after.owner == before.owner && after.kind != before.kind => {
+            format!("={}", after.kind)
+        }
+        Some(after) if after.owner == before.owner && after.promoted && !before.promoted => {
+            String::from("=+")
+        }
+        _ => String::new(),
+    }
+}
+
+// a piece dropped from hand, e.g. P@e4
+pub fn drop(state: &GameState, kind: PieceKind, to: &Vector) -> String {
+    let square = state.variant.notation(to).expect("drops are on the board");
+    format!("{}@{}", kind, square)
+}
+
+// + if the move left the player to move in check, # if in checkmate, in the position after the move
+pub fn check(state: &GameState) -> &'static str {
+    match state.get_player(state.turn()).state {
+        State::Check => "+",
+        State::Checkmate => "#",
+        _ => "",
+    }
+}
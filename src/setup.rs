@@ -0,0 +1,254 @@
+use crate::board::{Board, Vector};
+use crate::game::{castling_rights, double_move, far_ranks, pawns_moved};
+use crate::piece::{Piece, PieceKind, PlayerId};
+use crate::variant::Variant;
+
+/*
+ * A starting position and the rules that come with it, described in a text file, e.g.
+ *
+ *     # anything after a # is ignored
+ *     variant standard           # rules the setup doesn't cover, e.g. how the game is won
+ *     size 8 8                   # files, ranks
+ *     placement 4k3/8/8/8/8/8/8/4K3   # FEN piece placement, uppercase for player 1
+ *     piece 1 R a1               # owner, piece, square
+ *     hole d4 e4 d5 e5           # squares missing from the board
+ *     player 0 1                 # one per player, the direction their P move in
+ *     player 0 -1
+ *     royal K                    # the piece each player must keep safe, - for none
+ *     castling R                 # what a K may castle with, - for none
+ *     rights KQkq                # FEN castling rights, without this line every K may castle
+ *     passant e3                 # FEN en passant square, the last player just double moved past it
+ *     moved e1 h1                # pieces which have already moved, and so can't castle or double move
+ *     promotion 1 8              # player, then the ranks their P promote on
+ *     hand 2 QN                  # player, then the pieces they start with in hand
+ *
+ * every line is optional, but either size or placement has to give the board's dimensions
+ * without player lines there are two players, player 1 moving up the board
+ */
+#[derive(Clone)]
+pub struct Setup {
+    pub variant: Variant,
+    pub board: Board,
+    pub directions: Vec<Vector>, // one per player, players[0] corresponds to player 1
+    pub royal: Option<PieceKind>,
+    pub castling: Vec<PieceKind>,
+    pub promotion_ranks: Vec<Vec<i32>>, // one per player, see Player::promotion_ranks
+    pub hands: Vec<Vec<PieceKind>>,     // one per player, pieces to be dropped
+    pub passant: Option<Vector>,        // the square the last player's P passed in a double move
+}
+
+impl Default for Setup {
+    fn default() -> Self {
+        Self::from_variant(Variant::Standard)
+    }
+}
+
+impl Setup {
+    // the starting position of a variant, as a setup
+    pub fn from_variant(variant: Variant) -> Setup {
+        let fields: Vec<&str> = variant.start_fen().split_whitespace().collect();
+        let (placement, rights, passant) = (fields[0], fields[2], fields[3]);
+        let mut text = format!("variant {}", variant.name());
+        // pieces in hand follow the placement, e.g. [KMRRSSNNkmrrssnn]
+        let (placement, pocket) = placement.split_once('[').unwrap_or((placement, ""));
+        text.push_str(&format!("\nplacement {}", placement));
+        let white: String = pocket.chars().filter(char::is_ascii_uppercase).collect();
+        let black: String = pocket.chars().filter(char::is_ascii_lowercase).collect();
+        for (player, hand) in [(1, white), (2, black)] {
+            if !hand.is_empty() {
+                text.push_str(&format!("\nhand {} {}", player, hand.to_ascii_uppercase()));
+            }
+        }
+        text.push_str(&format!("\nrights {}", rights));
+        if passant != "-" {
+            text.push_str(&format!("\npassant {}", passant));
+        }
+        Self::parse(&text).expect("starting positions are valid FEN")
+    }
+    pub fn parse(text: &str) -> Result<Setup, &'static str> {
+        let mut variant = Variant::Standard;
+        let mut size = None;
+        let mut placement = None;
+        let mut pieces = Vec::new();
+        let mut holes = Vec::new();
+        let mut directions = Vec::new();
+        let mut royal = Some(PieceKind::King);
+        let mut castling = vec![PieceKind::Rook];
+        let mut promotions = Vec::new();
+        let mut held = Vec::new();
+        let mut rights = None;
+        let mut passant = None;
+        let mut moved = Vec::new();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            let mut words = line.split_whitespace();
+            let key = match words.next() {
+                Some(key) => key,
+                None => continue,
+            };
+            let args: Vec<&str> = words.collect();
+            match (key, args.as_slice()) {
+                ("variant", [name]) => {
+                    variant = Variant::from_name(name).ok_or("unknown variant in setup")?;
+                }
+                ("size", [files, ranks]) => {
+                    let files: usize = files.parse().map_err(|_| "size must be two numbers")?;
+                    let ranks: usize = ranks.parse().map_err(|_| "size must be two numbers")?;
+                    if files == 0 || ranks == 0 {
+                        return Err("the board needs at least one square");
+                    }
+                    size = Some((ranks, files));
+                }
+                ("placement", [fen]) => placement = Some(Board::from_fen(fen)?),
+                ("piece", [owner, id, square]) => {
+                    let owner: usize = owner.parse().map_err(|_| "owner must be a number")?;
+                    let mut chars = id.chars();
+                    let id = match (chars.next(), chars.next()) {
+                        (Some(id), None) if id.is_ascii_uppercase() => id,
+                        _ => return Err("pieces must be a single uppercase letter"),
+                    };
+                    pieces.push((PlayerId(owner), id, Vector::from_notation(square)?));
+                }
+                ("hole", squares) => {
+                    for square in squares {
+                        let pos = Vector::from_notation(square)?;
+                        if holes.contains(&pos) {
+                            return Err("the same hole is listed twice");
+                        }
+                        holes.push(pos);
+                    }
+                }
+                ("player", [x, y]) => {
+                    let x: i32 = x.parse().map_err(|_| "direction must be two numbers")?;
+                    let y: i32 = y.parse().map_err(|_| "direction must be two numbers")?;
+                    if x.abs() + y.abs() != 1 {
+                        return Err("direction must point along a rank or file");
+                    }
+                    directions.push(Vector(x, y));
+                }
+                ("royal", ["-"]) => royal = None,
+                ("royal", [id]) => {
+                    let mut chars = id.chars();
+                    royal = match (chars.next(), chars.next()) {
+                        (Some(id), None) if id.is_ascii_uppercase() => {
+                            Some(PieceKind::from_char(id))
+                        }
+                        _ => return Err("the royal piece must be a single uppercase letter"),
+                    };
+                }
+                ("castling", ["-"]) => castling = Vec::new(),
+                ("castling", ids) => {
+                    let ids = ids.iter().flat_map(|id| id.chars());
+                    castling = ids.map(PieceKind::from_char).collect();
+                }
+                ("promotion", [player, ranks @ ..]) => {
+                    let player: usize = player.parse().map_err(|_| "player must be a number")?;
+                    let mut lines = Vec::new();
+                    for rank in ranks {
+                        let rank: i32 = rank.parse().map_err(|_| "ranks must be numbers")?;
+                        lines.push(rank - 1);
+                    }
+                    promotions.push((player, lines));
+                }
+                ("rights", [field]) => rights = Some(*field),
+                ("passant", [square]) => passant = Some(Vector::from_notation(square)?),
+                ("moved", squares) => {
+                    for square in squares {
+                        moved.push(Vector::from_notation(square)?);
+                    }
+                }
+                ("hand", [player, ids]) => {
+                    let player: usize = player.parse().map_err(|_| "player must be a number")?;
+                    if !ids.chars().all(|id| id.is_ascii_uppercase()) {
+                        return Err("pieces in hand must be uppercase letters");
+                    }
+                    held.push((
+                        player,
+                        ids.chars().map(PieceKind::from_char).collect::<Vec<_>>(),
+                    ));
+                }
+                _ => return Err("unknown or malformed setup line"),
+            }
+        }
+        let mut board = match (placement, size) {
+            (Some(board), Some(size)) if board.shape() != size => {
+                return Err("placement does not match the size");
+            }
+            (Some(board), _) => board,
+            (None, Some((ranks, files))) => Board::new(ranks, files),
+            (None, None) => return Err("setup needs a size or a placement"),
+        };
+        if directions.is_empty() {
+            directions = vec![Vector(0, 1), Vector(0, -1)];
+        }
+        for (owner, id, pos) in pieces {
+            if owner == PlayerId::NEUTRAL || !board.in_bounds(&pos) {
+                return Err("pieces need an owner and a square on the board");
+            }
+            board[&pos] = Some(Piece::new(PieceKind::from_char(id), owner));
+        }
+        for pos in holes {
+            if !board.in_bounds(&pos) {
+                return Err("hole is off the board");
+            }
+            board[&pos] = Some(Piece::hole());
+        }
+        // P can only double move from their starting ranks, and K only castle if the rights say so
+        pawns_moved(&mut board, &directions, variant);
+        if let Some(rights) = rights {
+            castling_rights(&mut board, rights, &castling)?;
+        }
+        for pos in moved {
+            let piece = match board.in_bounds(&pos) {
+                true => board[&pos].as_mut(),
+                false => None,
+            };
+            piece.ok_or("moved piece is not on the board")?.has_moved = true;
+        }
+        // the last player to move before player 1 is the one who double moved
+        if let Some(passed) = &passant {
+            let mover = PlayerId(directions.len());
+            double_move(&board, passed, &directions[directions.len() - 1], mover)?;
+        }
+        let mut owners = board
+            .0
+             .0
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|piece| piece.owner);
+        if owners.any(|owner| owner > PlayerId(directions.len())) {
+            return Err("piece belongs to a player the setup doesn't have");
+        }
+        // P promote on the far ranks unless the setup says otherwise
+        let shape = board.shape();
+        let zone = variant.promotion_zone();
+        let mut promotion_ranks: Vec<Vec<i32>> = directions
+            .iter()
+            .map(|direction| far_ranks(direction, shape, zone))
+            .collect();
+        for (player, lines) in promotions {
+            if player == 0 || player > directions.len() {
+                return Err("promotion for a player the setup doesn't have");
+            }
+            promotion_ranks[player - 1] = lines;
+        }
+        let mut hands = vec![Vec::new(); directions.len()];
+        for (player, ids) in held {
+            if player == 0 || player > directions.len() {
+                return Err("hand for a player the setup doesn't have");
+            }
+            hands[player - 1].extend(ids);
+        }
+        Ok(Setup {
+            variant,
+            board,
+            directions,
+            royal,
+            castling,
+            promotion_ranks,
+            hands,
+            passant,
+        })
+    }
+}
@@ -0,0 +1,237 @@
+use crate::game::Game;
+use crate::input::{parse, Input};
+use crate::outcome::Outcome;
+use crate::piece::PlayerId;
+use crate::variant::Variant;
+use std::fmt;
+
+/*
+ * A simultaneous exhibition, one simul giver playing the same side on many boards at once
+ * the giver walks from board to board in order, moving on each board where it is their turn,
+ * while every opponent only ever moves on their own board
+ * a session is saved as text, and replayed from the inputs made on each board, e.g.
+ *
+ *     variant standard
+ *     giver 1            # the player the giver is on every board
+ *     boards 2
+ *     current 2          # the board the giver is at
+ *     moves 1 e2 e4 e7 e5
+ *     moves 2 e2 e4
+ */
+pub struct Simul {
+    pub boards: Vec<Game>,
+    pub giver: PlayerId,
+    current: usize,           // index into boards
+    records: Vec<Vec<Input>>, // every input accepted on each board, for saving
+}
+
+// results so far, from the simul giver's side
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Score {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub playing: usize, // boards which haven't finished
+}
+
+impl Score {
+    // a win is worth 1, a draw 1/2
+    pub fn points(&self) -> f32 {
+        self.wins as f32 + self.draws as f32 / 2.0
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "+{} ={} -{} ({} points, {} playing)",
+            self.wins,
+            self.draws,
+            self.losses,
+            self.points(),
+            self.playing
+        )
+    }
+}
+
+impl Simul {
+    pub fn new(variant: Variant, boards: usize, giver: PlayerId) -> Simul {
+        Simul {
+            boards: (0..boards).map(|_| Game::from_variant(variant)).collect(),
+            giver,
+            current: 0,
+            records: vec![Vec::new(); boards],
+        }
+    }
+    // the board the simul giver is at, counted from 0
+    pub fn current(&self) -> usize {
+        self.current
+    }
+    // whether the game on 'board' is waiting for the simul giver to move
+    pub fn awaits_giver(&self, board: usize) -> bool {
+        let game = &self.boards[board];
+        game.state.outcome.is_none() && game.state.turn() == self.giver
+    }
+    // every board waiting for the simul giver, in the order the giver visits them
+    pub fn awaiting_giver(&self) -> Vec<usize> {
+        (0..self.boards.len())
+            .filter(|board| self.awaits_giver(*board))
+            .collect()
+    }
+    /*
+     * makes an input on 'board', as in terminal play
+     * the simul giver may only play on the board they are at, once they have moved they go on
+     * to the next board waiting for them
+     */
+    pub fn play(&mut self, board: usize, input: Input) -> Result<(), &'static str> {
+        if board >= self.boards.len() {
+            return Err("there is no such board");
+        }
+        if self.awaits_giver(board) && board != self.current {
+            return Err("the simul giver is at another board");
+        }
+        apply(&mut self.boards[board], &input)?;
+        self.records[board].push(input);
+        self.advance();
+        Ok(())
+    }
+    // walks the simul giver on from the current board to the next one waiting for them, if any
+    fn advance(&mut self) {
+        if self.awaits_giver(self.current) {
+            return;
+        }
+        let boards = self.boards.len();
+        let next = (1..=boards)
+            .map(|offset| (self.current + offset) % boards)
+            .find(|board| self.awaits_giver(*board));
+        if let Some(next) = next {
+            self.current = next;
+        }
+    }
+    pub fn scoreboard(&self) -> Score {
+        let mut score = Score::default();
+        for game in &self.boards {
+            match &game.state.outcome {
+                Some(Outcome::Win(player_id, _)) if *player_id == self.giver => score.wins += 1,
+                Some(Outcome::Win(_, _)) => score.losses += 1,
+                Some(Outcome::Draw(_)) => score.draws += 1,
+                None => score.playing += 1,
+            }
+        }
+        score
+    }
+    // one of the boards, then where the session stands
+    pub fn draw(&self, board: usize) {
+        println!("board {}:", board + 1);
+        self.boards[board].draw();
+        let awaiting: Vec<String> = self
+            .awaiting_giver()
+            .iter()
+            .map(|board| (board + 1).to_string())
+            .collect();
+        println!("simul giver at board {}", self.current + 1);
+        println!("boards awaiting the simul giver: {}", awaiting.join(" "));
+        println!("score: {}", self.scoreboard());
+    }
+    // the whole session as text, see Simul
+    pub fn save(&self) -> String {
+        let variant = self.boards[0].state.variant;
+        let mut text = format!(
+            "variant {}\ngiver {}\nboards {}\ncurrent {}\n",
+            variant.name(),
+            self.giver,
+            self.boards.len(),
+            self.current + 1
+        );
+        for (board, record) in self.records.iter().enumerate() {
+            text.push_str(&format!("moves {}", board + 1));
+            for input in record {
+                text.push(' ');
+                text.push_str(&input.notation(variant).expect("inputs were on the board"));
+            }
+            text.push('\n');
+        }
+        text
+    }
+    // inverse of save
+    pub fn load(text: &str) -> Result<Simul, &'static str> {
+        let mut variant = Variant::Standard;
+        let mut giver = PlayerId(1);
+        let mut boards = None;
+        let mut current = 1;
+        let mut moves = Vec::new();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            let mut words = line.split_whitespace();
+            let key = match words.next() {
+                Some(key) => key,
+                None => continue,
+            };
+            let args: Vec<&str> = words.collect();
+            match (key, args.as_slice()) {
+                ("variant", [name]) => {
+                    variant = Variant::from_name(name).ok_or("unknown variant in simul")?;
+                }
+                ("giver", [player]) => {
+                    giver = PlayerId(player.parse().map_err(|_| "giver must be a number")?);
+                }
+                ("boards", [count]) => {
+                    boards = Some(count.parse().map_err(|_| "boards must be a number")?);
+                }
+                ("current", [board]) => {
+                    current = board.parse().map_err(|_| "current must be a number")?;
+                }
+                ("moves", [board, inputs @ ..]) => {
+                    let board: usize = board.parse().map_err(|_| "board must be a number")?;
+                    moves.push((board, inputs.to_vec()));
+                }
+                _ => return Err("unknown or malformed simul line"),
+            }
+        }
+        let boards = boards.ok_or("simul needs a number of boards")?;
+        if boards == 0 || current == 0 || current > boards {
+            return Err("the simul giver must be at one of the boards");
+        }
+        let mut simul = Simul::new(variant, boards, giver);
+        if giver == PlayerId::NEUTRAL || giver > PlayerId(simul.boards[0].state.num_players()) {
+            return Err("the simul giver must be one of the players");
+        }
+        // replayed board by board, so the giver's walk can't be checked and is restored after
+        for (board, inputs) in moves {
+            if board == 0 || board > boards {
+                return Err("moves for a board the simul doesn't have");
+            }
+            for input in inputs {
+                let input = parse(input, variant)?;
+                apply(&mut simul.boards[board - 1], &input)?;
+                simul.records[board - 1].push(input);
+            }
+        }
+        simul.current = current - 1;
+        Ok(simul)
+    }
+}
+
+// makes an input on a single game, as in terminal play
+fn apply(game: &mut Game, input: &Input) -> Result<(), &'static str> {
+    match input {
+        Input::Select(pos) if game.state.in_bounds(pos) => {
+            game.set_promotion(game.state.variant.promotion_pieces()[0]);
+            game.select(pos.clone());
+        }
+        Input::Promote(pos, id) if game.state.in_bounds(pos) => {
+            if !game.set_promotion(*id) {
+                return Err("cannot promote to that piece");
+            }
+            game.select(pos.clone());
+        }
+        Input::Drop(id, pos) => {
+            if !game.drop_piece(*id, pos) {
+                return Err("cannot drop that piece there");
+            }
+        }
+        _ => return Err("not on the board"),
+    }
+    Ok(())
+}
@@ -0,0 +1,267 @@
+use crate::annotation::Annotation;
+use crate::board::Vector;
+use crate::game::{GameState, Play};
+
+// index of a node in a MoveTree, stays the same for as long as the node does
+pub type NodeId = usize;
+
+// a position in the tree, and the move which led to it
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node {
+    pub state: GameState,
+    pub san: Option<String>, // the move in standard algebraic notation, None for the root
+    pub play: Option<Play>,  // the move as it was made, None for the root
+    pub duck: Option<Vector>, // in duck chess, where the duck was placed after the move
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>, // children[0] continues the main line, the rest are variations
+    pub annotation: Annotation, // comments, NAGs and so on written about the move
+}
+
+/*
+ * Every line played in a game, so stepping back and playing a different move keeps the old one
+ * the root is the starting position, each node's children are the moves tried from it,
+ * the first being the main line and the others variations
+ * deleted nodes leave their slot empty, so the ids of the others stay valid
+ */
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoveTree {
+    nodes: Vec<Option<Node>>,
+    current: NodeId,
+}
+
+impl MoveTree {
+    pub fn new(start: GameState) -> MoveTree {
+        MoveTree {
+            nodes: vec![Some(Node {
+                state: start,
+                san: None,
+                play: None,
+                duck: None,
+                parent: None,
+                children: Vec::new(),
+                annotation: Annotation::default(),
+            })],
+            current: 0,
+        }
+    }
+    pub const ROOT: NodeId = 0;
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id)?.as_ref()
+    }
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        self.nodes[id].as_mut().expect("node was deleted")
+    }
+    pub fn state_mut(&mut self, id: NodeId) -> Option<&mut GameState> {
+        Some(&mut self.nodes.get_mut(id)?.as_mut()?.state)
+    }
+    pub fn annotation_mut(&mut self, id: NodeId) -> Option<&mut Annotation> {
+        Some(&mut self.nodes.get_mut(id)?.as_mut()?.annotation)
+    }
+    // the node the game is at
+    pub fn current(&self) -> NodeId {
+        self.current
+    }
+    pub fn state(&self) -> &GameState {
+        &self.node(self.current).expect("current node exists").state
+    }
+    /*
+     * records a move from the current node, which becomes the new current node
+     * a move already tried from here is followed rather than added again,
+     * otherwise it starts the main line if there is none, or a new variation
+     */
+    pub fn play(
+        &mut self,
+        san: String,
+        play: Play,
+        duck: Option<Vector>,
+        state: GameState,
+    ) -> NodeId {
+        let children = &self
+            .node(self.current)
+            .expect("current node exists")
+            .children;
+        let existing = children
+            .iter()
+            .find(|child| self.nodes[**child].as_ref().unwrap().san.as_deref() == Some(&san));
+        if let Some(child) = existing.copied() {
+            self.node_mut(child).state = state;
+            self.current = child;
+            return child;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(Some(Node {
+            state,
+            san: Some(san),
+            play: Some(play),
+            duck,
+            parent: Some(self.current),
+            children: Vec::new(),
+            annotation: Annotation::default(),
+        }));
+        self.node_mut(self.current).children.push(id);
+        self.current = id;
+        id
+    }
+    // steps back to the position before the last move, false at the root
+    pub fn back(&mut self) -> bool {
+        match self.node(self.current).and_then(|node| node.parent) {
+            Some(parent) => {
+                self.current = parent;
+                true
+            }
+            None => false,
+        }
+    }
+    // steps forward along the main line from here, false at the end of a line
+    pub fn forward(&mut self) -> bool {
+        let children = &self
+            .node(self.current)
+            .expect("current node exists")
+            .children;
+        match children.first() {
+            Some(child) => {
+                self.current = *child;
+                true
+            }
+            None => false,
+        }
+    }
+    // jumps to any node in the tree, false if there is no such node
+    pub fn goto(&mut self, id: NodeId) -> bool {
+        if self.node(id).is_none() {
+            return false;
+        }
+        self.current = id;
+        true
+    }
+    // number of moves from the start to the node
+    pub fn ply(&self, id: NodeId) -> usize {
+        let mut ply = 0;
+        let mut node = self.node(id);
+        while let Some(parent) = node.and_then(|node| node.parent) {
+            ply += 1;
+            node = self.node(parent);
+        }
+        ply
+    }
+    // every node in the tree, each followed by the moves after it, main line first
+    pub fn preorder(&self) -> impl Iterator<Item = NodeId> {
+        let mut order = Vec::new();
+        let mut stack = vec![Self::ROOT];
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            let children = &self.node(id).expect("children exist").children;
+            stack.extend(children.iter().rev());
+        }
+        order.into_iter()
+    }
+    // every node from the start to the end of the main line
+    pub fn mainline(&self) -> Vec<NodeId> {
+        let mut line = vec![Self::ROOT];
+        let mut node = self.node(Self::ROOT);
+        while let Some(child) = node.and_then(|node| node.children.first()) {
+            line.push(*child);
+            node = self.node(*child);
+        }
+        line
+    }
+    // makes the line through 'id' the main line, every variation it branches off becomes one instead
+    pub fn promote(&mut self, id: NodeId) -> bool {
+        if self.node(id).is_none() {
+            return false;
+        }
+        let mut child = id;
+        while let Some(parent) = self.node(child).and_then(|node| node.parent) {
+            let siblings = &mut self.node_mut(parent).children;
+            let index = siblings
+                .iter()
+                .position(|sibling| *sibling == child)
+                .unwrap();
+            let promoted = siblings.remove(index);
+            siblings.insert(0, promoted);
+            child = parent;
+        }
+        true
+    }
+    /*
+     * removes the move at 'id' and every move after it, the root can't be removed
+     * if the game was somewhere in the removed line it goes back to where the line branched off
+     */
+    pub fn delete(&mut self, id: NodeId) -> bool {
+        let parent = match self.node(id).and_then(|node| node.parent) {
+            Some(parent) => parent,
+            None => return false,
+        };
+        self.node_mut(parent).children.retain(|child| *child != id);
+        let mut removed = vec![id];
+        while let Some(node) = removed.pop() {
+            if node == self.current {
+                self.current = parent;
+            }
+            if let Some(node) = self.nodes[node].take() {
+                removed.extend(node.children);
+            }
+        }
+        true
+    }
+    /*
+     * the moves of every line as PGN movetext, variations in brackets after the move they replace
+     * and annotations after the move they're about, e.g. 1. e4 e5 (1... c5! {Sicilian} 2. Nf3) 2. Nf3
+     */
+    pub fn movetext(&self) -> String {
+        let mut text = String::new();
+        let root = self.node(Self::ROOT).expect("the root is never deleted");
+        if let Some(comment) = root.annotation.comment_text(root.state.variant) {
+            text.push_str(&comment);
+            text.push(' ');
+        }
+        self.write_line(Self::ROOT, true, &mut text);
+        text.trim_end().to_string()
+    }
+    // writes the main line from 'id' on with its variations, 'numbered' if the next move needs its number
+    fn write_line(&self, id: NodeId, numbered: bool, text: &mut String) {
+        let children = &self.node(id).expect("children exist").children;
+        let main = match children.first() {
+            Some(main) => *main,
+            None => return,
+        };
+        let commented = self.write_move(id, main, numbered, text);
+        for variation in &children[1..] {
+            text.push('(');
+            let commented = self.write_move(id, *variation, true, text);
+            self.write_line(*variation, commented, text);
+            let end = text.trim_end().len();
+            text.truncate(end);
+            text.push_str(") ");
+        }
+        self.write_line(main, children.len() > 1 || commented, text);
+    }
+    /*
+     * writes the move from 'parent' to 'id' with its annotation, numbered if player 1 made it or
+     * 'numbered' is set, returns whether a comment was written, after which moves are numbered
+     */
+    fn write_move(&self, parent: NodeId, id: NodeId, numbered: bool, text: &mut String) -> bool {
+        let before = &self.node(parent).expect("parent exists").state;
+        let fullmove = before.halfmove_counter().div_ceil(2);
+        let first = before.turn().0 == 1;
+        if first {
+            text.push_str(&format!("{fullmove}. "));
+        } else if numbered {
+            text.push_str(&format!("{fullmove}... "));
+        }
+        let node = self.node(id).expect("children exist");
+        text.push_str(node.san.as_deref().unwrap_or(""));
+        text.push_str(&node.annotation.glyphs());
+        text.push(' ');
+        match node.annotation.comment_text(before.variant) {
+            Some(comment) => {
+                text.push_str(&comment);
+                text.push(' ');
+                true
+            }
+            None => false,
+        }
+    }
+}
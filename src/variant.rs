@@ -0,0 +1,296 @@
+use crate::board::Vector;
+use crate::cube::Vector3;
+use crate::hex;
+use crate::outcome::WinCondition;
+use crate::piece::PieceKind;
+use std::ops::RangeInclusive;
+
+// rulesets playable with the standard piece set plus the archbishop (A = B + N) and chancellor (C = R + N)
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant {
+    Standard,
+    Capablanca, // 10x8
+    Gothic,     // 10x8, Capablanca's pieces in a different order
+    Grand,      // 10x10, no castling, promotion anywhere in the last three ranks
+    Crazyhouse, // captured pieces change sides and can be dropped back onto the board
+    Bughouse,   // crazyhouse on two boards, captures are handed to the partner on the other board
+    Atomic,     // captures explode every piece but P around them, K cannot capture
+    ThreeCheck,
+    KingOfTheHill,
+    RacingKings, // no P, no checks, both K race to the 8th rank
+    Antichess,   // captures are compulsory, the K is an ordinary piece, lose everything to win
+    Horde,       // player 1 has 36 P and no K, player 2 wins by capturing all of them
+    FogOfWar,    // players only see squares their pieces can reach, there is no check
+    Duck,        // every move is followed by moving a neutral blocker, there is no check
+    Xiangqi,     // 9x10, played on the points, K A B N R C P are the xiangqi pieces
+    Shogi,       // 9x9, K R B G S N L P, captured pieces are dropped back by the captor
+    Hexagonal,   // Gliński's 91 hexagonal cells, see hex
+    Raumschach,  // 5x5x5, adds the unicorn (U), which moves through the corners of cells, see cube
+    Makruk, // Thai chess, the met (M) and khon (S) replace Q and B, P promote to M on the 6th rank
+    Sittuyin, // Burmese chess, makruk's pieces, players start by placing their pieces from hand
+}
+
+impl Variant {
+    pub fn from_name(name: &str) -> Option<Variant> {
+        match name.to_lowercase().as_str() {
+            "standard" => Some(Variant::Standard),
+            "capablanca" => Some(Variant::Capablanca),
+            "gothic" => Some(Variant::Gothic),
+            "grand" => Some(Variant::Grand),
+            "crazyhouse" => Some(Variant::Crazyhouse),
+            "bughouse" => Some(Variant::Bughouse),
+            "atomic" => Some(Variant::Atomic),
+            "threecheck" => Some(Variant::ThreeCheck),
+            "kingofthehill" => Some(Variant::KingOfTheHill),
+            "racingkings" => Some(Variant::RacingKings),
+            "antichess" => Some(Variant::Antichess),
+            "horde" => Some(Variant::Horde),
+            "fogofwar" => Some(Variant::FogOfWar),
+            "duck" => Some(Variant::Duck),
+            "xiangqi" => Some(Variant::Xiangqi),
+            "shogi" => Some(Variant::Shogi),
+            "hexagonal" => Some(Variant::Hexagonal),
+            "raumschach" => Some(Variant::Raumschach),
+            "makruk" => Some(Variant::Makruk),
+            "sittuyin" => Some(Variant::Sittuyin),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::Capablanca => "capablanca",
+            Variant::Gothic => "gothic",
+            Variant::Grand => "grand",
+            Variant::Crazyhouse => "crazyhouse",
+            Variant::Bughouse => "bughouse",
+            Variant::Atomic => "atomic",
+            Variant::ThreeCheck => "threecheck",
+            Variant::KingOfTheHill => "kingofthehill",
+            Variant::RacingKings => "racingkings",
+            Variant::Antichess => "antichess",
+            Variant::Horde => "horde",
+            Variant::FogOfWar => "fogofwar",
+            Variant::Duck => "duck",
+            Variant::Xiangqi => "xiangqi",
+            Variant::Shogi => "shogi",
+            Variant::Hexagonal => "hexagonal",
+            Variant::Raumschach => "raumschach",
+            Variant::Makruk => "makruk",
+            Variant::Sittuyin => "sittuyin",
+        }
+    }
+    pub fn start_fen(&self) -> &'static str {
+        match self {
+            Variant::Capablanca => {
+                "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1"
+            }
+            Variant::Gothic => {
+                "rnbqckabnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNBQCKABNR w KQkq - 0 1"
+            }
+            Variant::Grand => {
+                "r8r/1nbqkcabn1/pppppppppp/10/10/10/10/PPPPPPPPPP/1NBQKCABN1/R8R w - - 0 1"
+            }
+            Variant::RacingKings => "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+            Variant::Antichess => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            Variant::Horde => {
+                "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+            }
+            Variant::Xiangqi => {
+                "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1"
+            }
+            Variant::Shogi => "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - - 0 1",
+            // the 11x11 axial grid, - marks the corners which aren't cells
+            Variant::Hexagonal => concat!(
+                "1prnqb-----/2p2bk----/3p1b1n---/4p3r--/5ppppp-/11/",
+                "-PPPPP5/--R3P4/---N1B1P3/----QB2P2/-----BKNRP1 w - - 0 1"
+            ),
+            // levels A to E side by side, separated by the holes of cube
+            Variant::Raumschach => concat!(
+                "5--5--5--buqbu--rnknr/5--5--5--ppppp--ppppp/5--5--5--5--5/",
+                "PPPPP--PPPPP--5--5--5/RNKNR--BUQBU--5--5--5 w - - 0 1"
+            ),
+            Variant::Makruk => "rnsmksnr/8/pppppppp/8/8/PPPPPPPP/8/RNSKMSNR w - - 0 1",
+            // every piece but the P starts in hand
+            Variant::Sittuyin => "8/8/4pppp/pppp4/4PPPP/PPPP4/8/8[KMRRSSNNkmrrssnn] w - - 0 1",
+            _ => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        }
+    }
+    // how many squares the K travels towards the R when castling, None if castling is not allowed
+    pub fn castle_distance(&self) -> Option<i32> {
+        match self {
+            Variant::Capablanca | Variant::Gothic => Some(3),
+            Variant::Grand
+            | Variant::RacingKings
+            | Variant::Antichess
+            | Variant::Xiangqi
+            | Variant::Shogi
+            | Variant::Hexagonal
+            | Variant::Raumschach
+            | Variant::Makruk
+            | Variant::Sittuyin => None,
+            _ => Some(2),
+        }
+    }
+    // the ways this variant can end, checked in order after every move
+    pub fn win_conditions(&self) -> Vec<WinCondition> {
+        match self {
+            Variant::ThreeCheck => vec![WinCondition::Checkmate, WinCondition::ThreeCheck],
+            Variant::KingOfTheHill => vec![WinCondition::Checkmate, WinCondition::KingOfTheHill],
+            Variant::RacingKings => vec![WinCondition::RacingKings, WinCondition::Checkmate],
+            Variant::Antichess => vec![WinCondition::Antichess],
+            Variant::Horde => vec![WinCondition::Annihilation, WinCondition::Checkmate],
+            Variant::FogOfWar => vec![WinCondition::KingCapture],
+            Variant::Duck => vec![WinCondition::KingCapture, WinCondition::NoMoves],
+            Variant::Xiangqi => vec![WinCondition::Stalemate, WinCondition::Checkmate],
+            Variant::Shogi => vec![
+                WinCondition::Sennichite,
+                WinCondition::Stalemate,
+                WinCondition::Checkmate,
+            ],
+            Variant::Makruk => vec![WinCondition::Checkmate, WinCondition::Counting],
+            _ => vec![WinCondition::Checkmate],
+        }
+    }
+    // whether the K must be kept out of check, and losing it loses the game
+    pub fn royal_king(&self) -> bool {
+        !matches!(self, Variant::Antichess | Variant::FogOfWar | Variant::Duck)
+    }
+    // whether every move is followed by placing the duck
+    pub fn has_duck(&self) -> bool {
+        *self == Variant::Duck
+    }
+    // whether pieces stand on the points between squares, as in xiangqi
+    pub fn on_intersections(&self) -> bool {
+        *self == Variant::Xiangqi
+    }
+    // reads a square written in this variant's notation
+    pub fn square(&self, notation: &str) -> Result<Vector, &'static str> {
+        match self {
+            Variant::Hexagonal => hex::from_notation(notation),
+            Variant::Raumschach => Ok(Vector3::from_notation(notation)?.flatten()),
+            _ => Vector::from_notation(notation),
+        }
+    }
+    // inverse of square
+    pub fn notation(&self, pos: &Vector) -> Result<String, &'static str> {
+        match self {
+            Variant::Hexagonal => hex::to_notation(pos),
+            Variant::Raumschach => Vector3::unflatten(pos)
+                .ok_or("not a cell of the cube")?
+                .to_notation(),
+            _ => pos.to_notation(),
+        }
+    }
+    // whether the board is drawn as hexagons
+    pub fn hexagonal(&self) -> bool {
+        *self == Variant::Hexagonal
+    }
+    // whether the board is a cube, drawn one level at a time
+    pub fn three_dimensional(&self) -> bool {
+        *self == Variant::Raumschach
+    }
+    // whether promoted pieces are drawn differently, as in shogi
+    pub fn shows_promotions(&self) -> bool {
+        *self == Variant::Shogi
+    }
+    // whether each player only sees the squares their own pieces can reach
+    pub fn fog_of_war(&self) -> bool {
+        *self == Variant::FogOfWar
+    }
+    // whether a player who can capture has to
+    pub fn forced_captures(&self) -> bool {
+        *self == Variant::Antichess
+    }
+    // what a P may promote to, the first is the default, None declines to promote
+    // shogi pieces keep their kind when they promote, so Some(P) promotes in place and None declines
+    pub fn promotion_pieces(&self) -> &'static [Option<PieceKind>] {
+        match self {
+            Variant::Shogi => &[Some(PieceKind::Pawn), None],
+            Variant::Capablanca | Variant::Gothic => &[
+                Some(PieceKind::Queen),
+                Some(PieceKind::Custom('C')),
+                Some(PieceKind::Custom('A')),
+                Some(PieceKind::Rook),
+                Some(PieceKind::Bishop),
+                Some(PieceKind::Knight),
+            ],
+            // grand P may stay P short of the last rank, see Grand in ruleset
+            Variant::Grand => &[
+                Some(PieceKind::Queen),
+                Some(PieceKind::Custom('C')),
+                Some(PieceKind::Custom('A')),
+                Some(PieceKind::Rook),
+                Some(PieceKind::Bishop),
+                Some(PieceKind::Knight),
+                None,
+            ],
+            Variant::Antichess => &[
+                Some(PieceKind::Queen),
+                Some(PieceKind::Rook),
+                Some(PieceKind::Bishop),
+                Some(PieceKind::Knight),
+                Some(PieceKind::King),
+            ],
+            Variant::Raumschach => &[
+                Some(PieceKind::Queen),
+                Some(PieceKind::Rook),
+                Some(PieceKind::Bishop),
+                Some(PieceKind::Custom('U')),
+                Some(PieceKind::Knight),
+            ],
+            Variant::Makruk | Variant::Sittuyin => &[Some(PieceKind::Custom('M'))],
+            _ => &[
+                Some(PieceKind::Queen),
+                Some(PieceKind::Rook),
+                Some(PieceKind::Bishop),
+                Some(PieceKind::Knight),
+            ],
+        }
+    }
+    // the promotion written 'id' in SAN or at the terminal, e.g. the Q of e8=Q, or shogi's + and =
+    pub fn promotion_piece(&self, id: char) -> Option<PieceKind> {
+        match id {
+            '+' if self.shows_promotions() => Some(PieceKind::Pawn),
+            '=' => None,
+            id => Some(PieceKind::from_char(id)),
+        }
+    }
+    // inverse of promotion_piece
+    pub fn promotion_id(&self, promotion: Option<PieceKind>) -> char {
+        match promotion {
+            Some(_) if self.shows_promotions() => '+',
+            Some(kind) => kind.to_char(),
+            None => '=',
+        }
+    }
+    // whether players hold pieces in hand to be dropped, e.g. captured pieces in crazyhouse
+    pub fn has_drops(&self) -> bool {
+        matches!(
+            self,
+            Variant::Crazyhouse | Variant::Bughouse | Variant::Shogi | Variant::Sittuyin
+        )
+    }
+    // whether players begin by placing the pieces in their hand, before any piece moves
+    pub fn setup_phase(&self) -> bool {
+        *self == Variant::Sittuyin
+    }
+    // number of ranks, counted from the far edge, in which a P promotes, 0 if they never do
+    pub fn promotion_zone(&self) -> i32 {
+        match self {
+            Variant::Grand | Variant::Shogi | Variant::Makruk => 3,
+            // sittuyin P promote on the long diagonals instead, see Sittuyin in ruleset
+            Variant::Xiangqi | Variant::Sittuyin => 0,
+            _ => 1,
+        }
+    }
+    // rank indices (from the owner's side) that P start on, and may double move from
+    pub fn pawn_ranks(&self) -> RangeInclusive<usize> {
+        match self {
+            Variant::Grand => 2..=2,
+            Variant::Horde => 0..=1,
+            _ => 1..=1,
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use rust_chess::game::Game;
+use rust_chess::variant::Variant;
+
+#[test]
+fn captures_are_compulsory() {
+    let mut game = Game::from_variant(Variant::Antichess);
+    for san in "e3 b5".split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    assert!(game.play_san("Nf3").is_err());
+    assert!(game.play_san("Bxb5").is_ok());
+}
@@ -0,0 +1,26 @@
+use rust_chess::game::Game;
+use rust_chess::outcome::Outcome;
+use rust_chess::piece::{PieceKind, PlayerId};
+use rust_chess::variant::Variant;
+
+#[test]
+fn a_capture_explodes_everything_around_it_but_pawns() {
+    let mut game = Game::from_variant(Variant::Atomic);
+    for san in "Nf3 a6 Ne5 a5 Nxd7".split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    let kind_on = |square: &str| {
+        let pos = Variant::Atomic.square(square).unwrap();
+        game.state.get_piece(&pos).map(|piece| piece.kind)
+    };
+    // the N, the P it took, and the B, Q and K next to them are gone
+    for square in ["d7", "c8", "d8", "e8"] {
+        assert_eq!(kind_on(square), None, "{square}");
+    }
+    assert_eq!(kind_on("c7"), Some(PieceKind::Pawn));
+    assert_eq!(kind_on("e7"), Some(PieceKind::Pawn));
+    assert_eq!(
+        game.state.outcome,
+        Some(Outcome::Win(PlayerId(1), "destroying the king"))
+    );
+}
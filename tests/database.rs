@@ -0,0 +1,108 @@
+use rust_chess::database::{position_hash, Database, MoveStats, Query};
+use rust_chess::game::Game;
+
+// the last game reaches the second's final position by another order, and the start twice
+const GAMES: &str = r#"[Event "Paris Opera"]
+[Date "1858.11.02"]
+[White "Morphy"]
+[Black "Duke of Brunswick"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 1-0
+
+[Event "London"]
+[Date "1851.06.21"]
+[White "Anderssen"]
+[Black "Kieseritzky"]
+[Result "0-1"]
+
+1. e4 e5 2. Nf3 Nc6 0-1
+
+[Event "Paris"]
+[Date "1858.12.20"]
+[White "Morphy"]
+[Black "Anderssen"]
+[Result "1/2-1/2"]
+
+1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 e5 4. e4 Nc6 1/2-1/2
+"#;
+
+fn position(moves: &str) -> Game {
+    let mut game = Game::default();
+    for san in moves.split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    game
+}
+
+#[test]
+fn games_are_found_by_their_tags() {
+    let database = Database::from_pgn(GAMES).unwrap();
+    assert_eq!(database.len(), 3);
+    // empty fields match every game
+    let search = |player: &str, event: &str, date: &str, result: &str| {
+        let field = |text: &str| (!text.is_empty()).then(|| text.to_string());
+        database.search(&Query {
+            player: field(player),
+            event: field(event),
+            date: field(date),
+            result: field(result),
+        })
+    };
+    assert_eq!(search("morphy", "", "", ""), [0, 2]);
+    assert_eq!(search("Anderssen", "", "", ""), [1, 2]);
+    assert_eq!(search("", "paris", "", ""), [0, 2]);
+    assert_eq!(search("", "", "1858", ""), [0, 2]);
+    assert_eq!(search("", "", "1858.12", ""), [2]);
+    assert_eq!(search("", "", "", "0-1"), [1]);
+    assert_eq!(search("Morphy", "", "", "1/2-1/2"), [2]);
+    assert_eq!(search("", "", "", ""), [0, 1, 2]);
+    assert_eq!(database.tag(0, "Black"), Some("Duke of Brunswick"));
+    assert_eq!(database.result(1), Some("0-1"));
+}
+
+#[test]
+fn positions_are_found_however_they_were_reached() {
+    let database = Database::from_pgn(GAMES).unwrap();
+    // the last game reaches the start twice, but is listed once
+    assert_eq!(database.with_position(&Game::default().state), [0, 1, 2]);
+    let transposed = position("Nf3 e5 e4 Nc6");
+    assert_eq!(database.with_position(&transposed.state), [1, 2]);
+    let knight_out = position_hash(&position("Nf3").state);
+    assert_eq!(database.with_hash(knight_out), [2]);
+    assert!(database.with_position(&position("d4").state).is_empty());
+}
+
+#[test]
+fn material_signatures_are_found_in_any_order() {
+    let database = Database::from_pgn(GAMES).unwrap();
+    // after 5... dxe5 white has lost a N and a P, black a B and a P
+    let traded = "KQRRBBNPPPPPPPvKQRRBNNPPPPPPP";
+    assert_eq!(database.with_material(traded), [0]);
+    assert_eq!(database.with_material("PPPPPPPNBBRRQKvPPPPPPPNNBRRQK"), [0]);
+    let start = "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP";
+    assert_eq!(database.with_material(start), [0, 1, 2]);
+    assert!(database.with_material("KvK").is_empty());
+}
+
+#[test]
+fn move_stats_count_each_game_once() {
+    let database = Database::from_pgn(GAMES).unwrap();
+    let stats = |san: &str, games, wins, draws, losses| MoveStats {
+        san: san.to_string(),
+        games,
+        wins,
+        draws,
+        losses,
+    };
+    // the last game plays Nf3 from the start twice
+    assert_eq!(
+        database.move_stats(&Game::default().state),
+        [stats("e4", 2, 1, 0, 1), stats("Nf3", 1, 0, 1, 0)]
+    );
+    assert_eq!(
+        database.move_stats(&position("Nf3").state),
+        [stats("Nf6", 1, 0, 1, 0), stats("e5", 1, 0, 1, 0)]
+    );
+    assert!(database.move_stats(&position("d4").state).is_empty());
+}
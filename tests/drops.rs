@@ -0,0 +1,43 @@
+use rust_chess::board::Vector;
+use rust_chess::bughouse::Bughouse;
+use rust_chess::game::Game;
+use rust_chess::piece::{PieceKind, PlayerId};
+use rust_chess::variant::Variant;
+
+#[test]
+fn crazyhouse_captures_can_be_dropped_back() {
+    let mut game = Game::from_variant(Variant::Crazyhouse);
+    for san in "e4 d5 exd5 Qxd5 Nc3 Qa5".split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    assert_eq!(
+        game.state.get_player(PlayerId(1)).hand,
+        vec![PieceKind::Pawn]
+    );
+    assert_eq!(
+        game.state.get_player(PlayerId(2)).hand,
+        vec![PieceKind::Pawn]
+    );
+    game.play_san("P@d7+").unwrap();
+    assert!(game.state.get_player(PlayerId(1)).hand.is_empty());
+    assert!(game.pgn().contains("4. P@d7+"));
+}
+
+#[test]
+fn bughouse_captures_go_to_the_partner() {
+    let mut bughouse = Bughouse::new();
+    for (from, to) in [("e2", "e4"), ("d7", "d5"), ("e4", "d5")] {
+        for notation in [from, to] {
+            let pos = Vector::from_notation(notation).unwrap();
+            bughouse.select(0, pos);
+        }
+    }
+    assert!(bughouse
+        .board(0)
+        .state
+        .get_player(PlayerId(1))
+        .hand
+        .is_empty());
+    let partner = bughouse.board(1).state.get_player(PlayerId(2));
+    assert_eq!(partner.hand, vec![PieceKind::Pawn]);
+}
@@ -0,0 +1,13 @@
+use rust_chess::game::Game;
+use rust_chess::variant::Variant;
+
+#[test]
+fn the_duck_blocks_the_square_it_is_placed_on() {
+    let mut game = Game::from_variant(Variant::Duck);
+    game.play_san("e4,e5").unwrap();
+    let e5 = Variant::Duck.square("e5").unwrap();
+    assert!(game.state.get_piece(&e5).unwrap().is_duck());
+    assert!(game.play_san("e5").is_err());
+    assert!(game.play_san("d5,e3").is_ok());
+    assert!(game.pgn().contains("1. e4,e5 d5,e3"));
+}
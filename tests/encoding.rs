@@ -0,0 +1,39 @@
+use rust_chess::encoding::{Reader, Writer, MAGIC};
+use rust_chess::game::Game;
+use std::fs;
+use std::io::ErrorKind;
+
+#[test]
+fn archives_read_back_the_games_written() {
+    let game = Game::from_pgn(&fs::read_to_string("tests/opera.pgn").unwrap()).unwrap();
+    let mut writer = Writer::new(Vec::new()).unwrap();
+    writer.write_game(&game).unwrap();
+    writer.write_game(&game).unwrap();
+    let bytes = writer.into_inner();
+    let games: Vec<Game> = Reader::new(&bytes[..])
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[1].pgn(), game.pgn());
+}
+
+#[test]
+fn a_corrupt_length_is_an_error() {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend([0xff; 16]);
+    let err = Reader::new(&bytes[..]).unwrap().read_record().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn a_truncated_record_is_an_error() {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend([0x80]);
+    let err = Reader::new(&bytes[..]).unwrap().read_record().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend([0x7f, 1, 2, 3]);
+    let err = Reader::new(&bytes[..]).unwrap().read_record().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
@@ -0,0 +1,26 @@
+use rust_chess::game::Game;
+use rust_chess::outcome::Outcome;
+use rust_chess::piece::PlayerId;
+use rust_chess::variant::Variant;
+
+#[test]
+fn players_only_see_where_their_pieces_can_go() {
+    let game = Game::from_variant(Variant::FogOfWar);
+    let visible = game.visible_squares(PlayerId(1));
+    let square = |notation: &str| Variant::FogOfWar.square(notation).unwrap();
+    assert!(visible[&square("e4")]);
+    assert!(!visible[&square("e5")]);
+    assert!(!visible[&square("e7")]);
+}
+
+#[test]
+fn there_is_no_check_so_the_king_can_be_taken() {
+    let mut game = Game::from_variant(Variant::FogOfWar);
+    for san in "e4 e5 Qh5 Nc6 Qxf7 a6 Qxe8".split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    assert_eq!(
+        game.state.outcome,
+        Some(Outcome::Win(PlayerId(1), "capturing the king"))
+    );
+}
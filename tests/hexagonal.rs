@@ -0,0 +1,11 @@
+use rust_chess::game::Game;
+use rust_chess::variant::Variant;
+
+#[test]
+fn glinski_starts_with_51_moves() {
+    let game = Game::from_variant(Variant::Hexagonal);
+    // the number of first moves, as counted by other engines
+    assert_eq!(game.move_list(&game.state).len(), 51);
+    let f11 = Variant::Hexagonal.square("f11").unwrap();
+    assert_eq!(Variant::Hexagonal.notation(&f11).unwrap(), "f11");
+}
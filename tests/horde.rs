@@ -0,0 +1,14 @@
+use rust_chess::game::Game;
+use rust_chess::piece::{PieceKind, PlayerId};
+use rust_chess::variant::Variant;
+
+#[test]
+fn the_horde_is_pawns_against_a_full_army() {
+    let game = Game::from_variant(Variant::Horde);
+    let pieces = game.state.board.0 .0.iter().flatten().flatten();
+    let horde: Vec<_> = pieces.filter(|piece| piece.owner == PlayerId(1)).collect();
+    assert_eq!(horde.len(), 36);
+    assert!(horde.iter().all(|piece| piece.kind == PieceKind::Pawn));
+    // the number of first moves, as counted by other engines
+    assert_eq!(game.move_list(&game.state).len(), 8);
+}
@@ -0,0 +1,123 @@
+use rust_chess::game::{Game, GameState, Play};
+use rust_chess::piece::PieceKind;
+use rust_chess::variant::Variant;
+
+fn play(variant: Variant, moves: &str) -> Game {
+    let mut game = Game::from_variant(variant);
+    for san in moves.split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    game
+}
+
+fn kind_on(game: &Game, square: &str) -> Option<PieceKind> {
+    let pos = game.state.variant.square(square).unwrap();
+    game.state.get_piece(&pos).map(|piece| piece.kind)
+}
+
+#[test]
+fn large_boards_start_with_the_archbishop_and_chancellor() {
+    let capablanca = Game::from_variant(Variant::Capablanca);
+    assert_eq!(capablanca.state.board.shape(), (8, 10));
+    assert_eq!(kind_on(&capablanca, "c1"), Some(PieceKind::Custom('A')));
+    assert_eq!(kind_on(&capablanca, "h8"), Some(PieceKind::Custom('C')));
+    assert_eq!(kind_on(&capablanca, "j1"), Some(PieceKind::Rook));
+
+    let gothic = Game::from_variant(Variant::Gothic);
+    assert_eq!(kind_on(&gothic, "e1"), Some(PieceKind::Custom('C')));
+    assert_eq!(kind_on(&gothic, "g8"), Some(PieceKind::Custom('A')));
+
+    let grand = Game::from_variant(Variant::Grand);
+    assert_eq!(grand.state.board.shape(), (10, 10));
+    assert_eq!(kind_on(&grand, "a1"), Some(PieceKind::Rook));
+    assert_eq!(kind_on(&grand, "a3"), Some(PieceKind::Pawn));
+
+    // the number of first moves, as counted by other engines
+    assert_eq!(capablanca.move_list(&capablanca.state).len(), 28);
+    assert_eq!(grand.move_list(&grand.state).len(), 65);
+}
+
+#[test]
+fn fen_reads_files_beyond_h() {
+    let state = GameState::from_fen("k9/10/10/10/10/10/10/8CK w - - 0 1", Variant::Capablanca);
+    let game = Game::from_state(state.unwrap());
+    assert_eq!(kind_on(&game, "j1"), Some(PieceKind::King));
+    assert_eq!(kind_on(&game, "i1"), Some(PieceKind::Custom('C')));
+    assert!(
+        GameState::from_fen("k10/10/10/10/10/10/10/9K w - - 0 1", Variant::Capablanca).is_err()
+    );
+}
+
+#[test]
+fn the_archbishop_and_chancellor_move_as_b_or_r_and_as_n() {
+    // each jumps as N first, then slides
+    let game = play(
+        Variant::Capablanca,
+        "Nj3 a6 h3 a5 Ad3 a4 Cg3 a3 Af5 b6 Cxg7",
+    );
+    assert_eq!(kind_on(&game, "f5"), Some(PieceKind::Custom('A')));
+    assert_eq!(kind_on(&game, "g7"), Some(PieceKind::Custom('C')));
+    assert!(game.pgn().contains("5. Af5 b6 6. Cxg7"));
+}
+
+#[test]
+fn capablanca_castling_moves_the_k_three_squares() {
+    let game = play(Variant::Capablanca, "Nj3 a6 h3 a5 Bh2 a4 Cg3 a3 O-O");
+    assert_eq!(kind_on(&game, "i1"), Some(PieceKind::King));
+    assert_eq!(kind_on(&game, "h1"), Some(PieceKind::Rook));
+    assert_eq!(kind_on(&game, "j1"), None);
+    assert!(game.pgn().contains("5. O-O"));
+}
+
+fn grand(fen: &str) -> Game {
+    Game::from_state(GameState::from_fen(fen, Variant::Grand).unwrap())
+}
+
+fn promotions_from(game: &Game, square: &str) -> Vec<Option<PieceKind>> {
+    let from = game.state.variant.square(square).unwrap();
+    let plays = game.move_list(&game.state).into_iter();
+    plays
+        .filter_map(|play| match play {
+            Play::Move(start, _, promotion) if start == from => Some(promotion),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn grand_pawns_promote_in_the_last_three_ranks() {
+    // black has lost everything but the K, so may promote to any piece
+    let mut game = grand("k9/10/10/10/10/10/p9/10/10/9K b - - 0 1");
+    assert_eq!(
+        promotions_from(&game, "a4"),
+        Variant::Grand.promotion_pieces()
+    );
+    game.play_san("a3=C").unwrap();
+    assert_eq!(kind_on(&game, "a3"), Some(PieceKind::Custom('C')));
+    assert!(Game::from_variant(Variant::Grand).play_san("O-O").is_err());
+}
+
+#[test]
+fn grand_pawns_only_promote_to_pieces_they_have_lost() {
+    // white has every piece but the R, so the P on a7 may become a R or stay a P
+    let fen = "9k/10/10/P9/10/10/10/1PPPPPPPPP/1NBQKCABN1/10 w - - 0 1";
+    assert_eq!(
+        promotions_from(&grand(fen), "a7"),
+        [Some(PieceKind::Rook), None]
+    );
+    assert!(grand(fen).play_san("a8=Q").is_err());
+    let mut stays = grand(fen);
+    stays.play_san("a8").unwrap();
+    assert_eq!(kind_on(&stays, "a8"), Some(PieceKind::Pawn));
+
+    // on the last rank it has to promote
+    let fen = "9k/P9/10/10/10/10/10/1PPPPPPPPP/1NBQKCABN1/10 w - - 0 1";
+    assert_eq!(promotions_from(&grand(fen), "a9"), [Some(PieceKind::Rook)]);
+    let mut promoted = grand(fen);
+    promoted.play_san("a10=R").unwrap();
+    assert_eq!(kind_on(&promoted, "a10"), Some(PieceKind::Rook));
+
+    // with nothing lost it can't move there at all
+    let fen = "9k/P9/10/10/10/10/10/1PPPPPPPPP/1NBQKCABN1/R8R w - - 0 1";
+    assert!(promotions_from(&grand(fen), "a9").is_empty());
+}
@@ -0,0 +1,20 @@
+use rust_chess::game::{Game, Play};
+use rust_chess::piece::PieceKind;
+use rust_chess::variant::Variant;
+
+#[test]
+fn makruk_starts_with_23_moves() {
+    let game = Game::from_variant(Variant::Makruk);
+    // the number of first moves, as counted by other engines
+    assert_eq!(game.move_list(&game.state).len(), 23);
+}
+
+#[test]
+fn sittuyin_starts_by_placing_pieces() {
+    let mut game = Game::from_variant(Variant::Sittuyin);
+    let plays = game.move_list(&game.state);
+    assert!(plays.iter().all(|play| matches!(play, Play::Drop(..))));
+    game.play_san("K@e2").unwrap();
+    let e2 = Variant::Sittuyin.square("e2").unwrap();
+    assert_eq!(game.state.get_piece(&e2).unwrap().kind, PieceKind::King);
+}
@@ -0,0 +1,39 @@
+use rust_chess::game::Game;
+use rust_chess::opening::Classifier;
+use std::sync::OnceLock;
+
+// building the classifier plays every listed opening, so the tests share one
+fn classify(moves: &str) -> (&'static str, &'static str) {
+    static CLASSIFIER: OnceLock<Classifier> = OnceLock::new();
+    let classifier = CLASSIFIER.get_or_init(Classifier::new);
+    let mut game = Game::default();
+    for san in moves.split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    let opening = classifier.classify(&game).unwrap();
+    (opening.eco, opening.name)
+}
+
+#[test]
+fn deep_lines_are_named_after_the_last_opening_reached() {
+    assert_eq!(
+        classify(
+            "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7 Re1 b5 Bb3 O-O c3 d5 exd5 Nxd5 Nxe5 Nxe5 Rxe5 c6"
+        ),
+        ("C89", "Ruy Lopez, Marshall Attack")
+    );
+    // the Najdorf reached by a different move order, then the English Attack
+    assert_eq!(
+        classify("Nf3 c5 e4 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6 Be3 e5 Nb3 Be6 f3"),
+        ("B90", "Sicilian Defence, Najdorf Variation")
+    );
+}
+
+#[test]
+fn lines_missing_from_the_table_are_named_after_a_listed_one() {
+    // the Meran has codes of its own, D46 to D49, which aren't listed
+    assert_eq!(
+        classify("d4 d5 c4 c6 Nf3 Nf6 Nc3 e6 e3 Nbd7 Bd3 dxc4 Bxc4 b5"),
+        ("D43", "Semi-Slav Defence")
+    );
+}
@@ -0,0 +1,19 @@
+use rust_chess::game::Game;
+use rust_chess::pgn;
+
+#[test]
+fn null_annotations_are_read_and_written() {
+    let game = Game::from_pgn("1. e4 $0 e5 $0 $1 2. Nf3 *").unwrap();
+    let text = game.pgn();
+    assert!(text.ends_with("\n1. e4 $0 e5 $0 $1 2. Nf3 *\n"), "{text}");
+    let again = Game::from_pgn(&text).unwrap();
+    assert_eq!(again.pgn(), text);
+}
+
+#[test]
+fn glyphs_and_comments_survive_a_round_trip() {
+    let text = "1. e4! {best by test [%clk 0:05:00]} 1... e5?! (1... c5 $14) 2. Nf3 *";
+    let game = Game::from_pgn(text).unwrap();
+    assert!(game.pgn().contains(text), "{}", game.pgn());
+    assert_eq!(pgn::result(&game), "*");
+}
@@ -0,0 +1,57 @@
+use rust_chess::game::{Game, GameState, Play};
+use rust_chess::piece::PieceKind;
+use rust_chess::variant::Variant;
+
+fn shogi(moves: &str) -> Game {
+    let mut game = Game::from_variant(Variant::Shogi);
+    for san in moves.split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    game
+}
+
+#[test]
+fn a_pawn_promotes_to_the_piece_chosen() {
+    let state = GameState::from_fen("8/P7/8/8/8/8/8/k6K w - - 0 1", Variant::Standard).unwrap();
+    let (a7, a8) = (
+        Variant::Standard.square("a7").unwrap(),
+        Variant::Standard.square("a8").unwrap(),
+    );
+    let game = Game::from_state(state.clone());
+    let promotions: Vec<Option<PieceKind>> = game
+        .move_list(&game.state)
+        .into_iter()
+        .filter_map(|play| match play {
+            Play::Move(from, _, promotion) if from == a7 => Some(promotion),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(promotions, Variant::Standard.promotion_pieces());
+
+    let mut game = Game::from_state(state.clone());
+    game.play_san("a8=N").unwrap();
+    assert_eq!(game.state.get_piece(&a8).unwrap().kind, PieceKind::Knight);
+    let mut game = Game::from_state(state);
+    assert!(game.play(&Play::Move(a7, a8.clone(), Some(PieceKind::Rook))));
+    assert_eq!(game.state.get_piece(&a8).unwrap().kind, PieceKind::Rook);
+    assert!(game.pgn().contains("1. a8=R"));
+}
+
+#[test]
+fn shogi_pieces_promote_only_when_the_move_says_so() {
+    let (b2, h8) = (
+        Variant::Shogi.square("b2").unwrap(),
+        Variant::Shogi.square("h8").unwrap(),
+    );
+    let mut declined = shogi("c4 g6");
+    assert!(declined.play(&Play::Move(b2.clone(), h8.clone(), None)));
+    let bishop = declined.state.get_piece(&h8).unwrap();
+    assert_eq!(bishop.kind, PieceKind::Bishop);
+    assert!(!bishop.promoted);
+
+    let promoted = shogi("c4 g6 Bxh8=+");
+    let bishop = promoted.state.get_piece(&h8).unwrap();
+    assert_eq!(bishop.kind, PieceKind::Bishop);
+    assert!(bishop.promoted);
+    assert!(!shogi("c4 g6").set_promotion(Some(PieceKind::Queen)));
+}
@@ -0,0 +1,16 @@
+use rust_chess::game::Game;
+use rust_chess::piece::PieceKind;
+use rust_chess::variant::Variant;
+
+#[test]
+fn the_unicorn_moves_through_the_corners_of_cells() {
+    let mut game = Game::from_variant(Variant::Raumschach);
+    game.play_san("UDd3").unwrap();
+    let cell = Variant::Raumschach.square("Dd3").unwrap();
+    assert_eq!(
+        game.state.get_piece(&cell).unwrap().kind,
+        PieceKind::Custom('U')
+    );
+    assert_eq!(Variant::Raumschach.notation(&cell).unwrap(), "Dd3");
+    assert!(game.pgn().contains("1. UDd3"));
+}
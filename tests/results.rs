@@ -0,0 +1,77 @@
+use rust_chess::database::Database;
+use rust_chess::encoding;
+use rust_chess::game::Game;
+use rust_chess::outcome::{GameResult, Outcome};
+use rust_chess::pgn;
+use rust_chess::piece::PlayerId;
+
+fn play(moves: &str) -> Game {
+    let mut game = Game::default();
+    for san in moves.split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    game
+}
+
+#[test]
+fn resigning_after_an_undo_keeps_the_later_moves() {
+    let mut game = play("e4 e5 Nf3 Nc6");
+    assert!(game.undo() && game.undo());
+    assert!(game.resign(PlayerId(1)));
+    assert_eq!(game.result(), GameResult::Win(PlayerId(2)));
+    assert_eq!(game.tree().mainline().len(), 5);
+    assert_eq!(game.tree().ply(game.finished().unwrap()), 2);
+    assert_eq!(pgn::result(&game), "0-1");
+    assert!(game.pgn().ends_with("\n1. e4 e5 2. Nf3 Nc6 0-1\n"));
+    assert!(game.play_san("Nf3").is_err());
+    // the moves taken back can still be looked at, but not played on from
+    assert!(game.redo());
+    assert_eq!(game.result(), GameResult::Win(PlayerId(2)));
+    assert!(game.play_san("Bc4").is_err());
+
+    let read = Game::from_pgn(&game.pgn()).unwrap();
+    assert_eq!(pgn::result(&read), "0-1");
+    let decoded = encoding::decode(&encoding::encode(&game).unwrap()).unwrap();
+    assert_eq!(pgn::result(&decoded), "0-1");
+    assert_eq!(decoded.tree().mainline().len(), 5);
+    assert_eq!(decoded.tree().ply(decoded.finished().unwrap()), 2);
+    let database = Database::from_pgn(&game.pgn()).unwrap();
+    assert_eq!(database.result(0), Some("0-1"));
+}
+
+#[test]
+fn a_draw_agreed_in_a_variation_leaves_the_main_line_alone() {
+    let mut game = play("e4 e5 Nf3");
+    assert!(game.back() && game.back());
+    game.play_san("c5").unwrap();
+    assert!(game.offer_draw(PlayerId(1)));
+    assert!(!game.accept_draw(PlayerId(1)));
+    assert!(game.accept_draw(PlayerId(2)));
+    assert_eq!(game.state.outcome, Some(Outcome::Draw("agreement")));
+    assert_eq!(pgn::result(&game), "1/2-1/2");
+    assert!(game.pgn().contains("1. e4 e5 (1... c5) 2. Nf3 1/2-1/2"));
+}
+
+#[test]
+fn moving_declines_a_draw_offer() {
+    let mut game = play("e4");
+    assert!(game.offer_draw(PlayerId(2)));
+    game.play_san("e5").unwrap();
+    assert_eq!(game.draw_offer(), Some(PlayerId(2)));
+    game.play_san("Nf3").unwrap();
+    assert_eq!(game.draw_offer(), None);
+    assert!(!game.accept_draw(PlayerId(1)));
+}
+
+#[test]
+fn encoding_keeps_how_the_game_ended() {
+    let mut game = Game::from_pgn("[Result \"*\"]\n\n1. e4 e5 *").unwrap();
+    assert!(game.offer_draw(PlayerId(1)));
+    assert!(game.accept_draw(PlayerId(2)));
+    let mut decoded = encoding::decode(&encoding::encode(&game).unwrap()).unwrap();
+    assert_eq!(decoded.state.outcome, Some(Outcome::Draw("agreement")));
+    assert_eq!(decoded.result(), GameResult::Draw);
+    let results = decoded.tags.iter().filter(|(name, _)| name == "Result");
+    assert_eq!(results.count(), 1);
+    assert!(decoded.play_san("Nf3").is_err());
+}
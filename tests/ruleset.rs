@@ -0,0 +1,129 @@
+use rust_chess::board::{Matrix, Vector};
+use rust_chess::game::{Game, GameState, Play};
+use rust_chess::outcome::{GameResult, Outcome};
+use rust_chess::piece::{PieceKind, PlayerId};
+use rust_chess::ruleset::{self, Ruleset, StandardChess};
+use rust_chess::variant::Variant;
+
+// crazyhouse, except that pieces in hand can never be dropped and positions are never remembered
+struct Hoarding(Box<dyn Ruleset>);
+
+impl Ruleset for Hoarding {
+    fn start(&self) -> GameState {
+        self.0.start()
+    }
+    fn capture(&self, state: &mut GameState, pos: &Vector) {
+        self.0.capture(state, pos)
+    }
+    fn drop_squares(&self, state: &GameState, _: PieceKind, _: PlayerId) -> Matrix<bool> {
+        let (rows, cols) = state.board.shape();
+        Matrix::new(false, rows, cols)
+    }
+    fn record_position(&self, _: &mut GameState) {}
+}
+
+// standard chess, except that P never move two squares
+struct Timid(StandardChess);
+
+impl Ruleset for Timid {
+    fn start(&self) -> GameState {
+        self.0.start()
+    }
+    fn claim_squares(&self, state: &GameState, pos: &Vector) -> (Matrix<bool>, Matrix<bool>) {
+        let piece = state.get_piece(pos).unwrap();
+        let (mut valid, threat) = piece.claim_squares()(pos, state, self);
+        if piece.kind == PieceKind::Pawn {
+            let (rows, cols) = state.board.shape();
+            for row in 0..rows as i32 {
+                for col in 0..cols as i32 {
+                    valid[&Vector(col, row)] &= (row - pos.1).abs() < 2;
+                }
+            }
+        }
+        (valid, threat)
+    }
+}
+
+// standard chess, except that the first capture wins
+struct FirstBlood(StandardChess);
+
+impl Ruleset for FirstBlood {
+    fn start(&self) -> GameState {
+        self.0.start()
+    }
+    fn outcome(&self, state: &GameState) -> Option<Outcome> {
+        let first = state.captured.first()?;
+        Some(Outcome::Win(
+            PlayerId(3 - first.owner.0),
+            "drawing first blood",
+        ))
+    }
+}
+
+fn play(game: &mut Game, moves: &str) {
+    for san in moves.split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+}
+
+#[test]
+fn the_ruleset_decides_where_pieces_are_dropped() {
+    let mut standard = Game::from_variant(Variant::Crazyhouse);
+    let mut hoarding = Game::with_rules(Box::new(Hoarding(ruleset::from_variant(
+        Variant::Crazyhouse,
+    ))));
+    for game in [&mut standard, &mut hoarding] {
+        play(game, "e4 d5 exd5");
+        assert_eq!(
+            game.state.get_player(PlayerId(1)).hand,
+            vec![PieceKind::Pawn]
+        );
+        play(game, "Qxd5");
+    }
+    let drops = |game: &Game| {
+        let plays = game.move_list(&game.state);
+        plays
+            .iter()
+            .filter(|play| matches!(play, Play::Drop(..)))
+            .count()
+    };
+    assert!(drops(&standard) > 0);
+    assert_eq!(drops(&hoarding), 0);
+    assert!(!hoarding.drop_piece(PieceKind::Pawn, &Vector(4, 3)));
+    assert!(hoarding.play_san("P@e6").is_err());
+}
+
+#[test]
+fn the_ruleset_decides_which_positions_are_remembered() {
+    let standard = Game::from_variant(Variant::Crazyhouse);
+    let mut hoarding = Game::with_rules(Box::new(Hoarding(ruleset::from_variant(
+        Variant::Crazyhouse,
+    ))));
+    assert_eq!(standard.state.positions.len(), 1);
+    assert!(hoarding.state.positions.is_empty());
+    play(&mut hoarding, "Nf3 Nf6 Ng1 Ng8");
+    assert!(hoarding.state.positions.is_empty());
+}
+
+#[test]
+fn the_ruleset_decides_how_pieces_move() {
+    let mut timid = Game::with_rules(Box::new(Timid(StandardChess::default())));
+    // 8 P and 2 N, each with two moves
+    assert_eq!(timid.move_list(&timid.state).len(), 20 - 8);
+    assert!(timid.play_san("e4").is_err());
+    play(&mut timid, "e3 e6 Qh5 Ke7");
+    assert_eq!(timid.play_san("Qxf7"), Ok(()));
+    assert_eq!(timid.result(), GameResult::Ongoing);
+    // the K's escape squares are worked out by the same rules
+    assert!(timid.play_san("Ke8").is_err());
+}
+
+#[test]
+fn the_ruleset_decides_when_the_game_ends() {
+    let mut first_blood = Game::with_rules(Box::new(FirstBlood(StandardChess::default())));
+    play(&mut first_blood, "e4 d5");
+    assert_eq!(first_blood.result(), GameResult::Ongoing);
+    play(&mut first_blood, "exd5");
+    assert_eq!(first_blood.result(), GameResult::Win(PlayerId(1)));
+    assert_eq!(first_blood.play_san("Qxd5"), Err("the game is over"));
+}
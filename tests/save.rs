@@ -0,0 +1,46 @@
+#[cfg(feature = "serde")]
+use rust_chess::{game::Game, outcome::GameResult, piece::PlayerId, variant::Variant};
+
+#[cfg(feature = "serde")]
+#[test]
+fn a_saved_game_loads_with_its_variations_hands_result_and_redo() {
+    let mut game = Game::from_variant(Variant::Crazyhouse);
+    for san in "e4 d5 exd5 Qxd5 Nc3 Qa5".split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    game.rewind(1);
+    game.play_san("Qd8").unwrap();
+    game.annotate().comment = "back home".to_string();
+    assert!(game.undo() && game.undo());
+    assert!(game.resign(PlayerId(2)));
+
+    let path = std::env::temp_dir().join(format!("rust-chess-save-{}.json", std::process::id()));
+    game.save(&path).unwrap();
+    let mut loaded = Game::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.pgn(), game.pgn());
+    assert!(
+        loaded
+            .pgn()
+            .contains("3. Nc3 Qa5 (3... Qd8 {back home}) 1-0"),
+        "{}",
+        loaded.pgn()
+    );
+    assert_eq!(loaded.tree().current(), game.tree().current());
+    assert_eq!(loaded.result(), GameResult::Win(PlayerId(1)));
+    assert_eq!(loaded.finished(), game.finished());
+    for player in [PlayerId(1), PlayerId(2)] {
+        assert_eq!(
+            loaded.state.get_player(player).hand,
+            game.state.get_player(player).hand
+        );
+    }
+    assert_eq!(loaded.state.get_player(PlayerId(1)).hand.len(), 1);
+    // the two moves taken back are redone in order
+    assert!(loaded.redo() && game.redo());
+    assert_eq!(loaded.tree().current(), game.tree().current());
+    assert!(loaded.redo() && game.redo());
+    assert_eq!(loaded.tree().current(), game.tree().current());
+    assert!(!loaded.redo());
+}
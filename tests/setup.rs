@@ -0,0 +1,87 @@
+use rust_chess::game::Game;
+use rust_chess::piece::PieceKind;
+use rust_chess::setup::Setup;
+use rust_chess::variant::Variant;
+
+#[test]
+fn a_custom_setup_has_holes_and_its_own_royal_piece() {
+    let setup = Setup::parse(include_str!("custom.setup")).unwrap();
+    let game = Game::new(&setup);
+    assert_eq!(game.state.board.shape(), (6, 6));
+    assert!(!game
+        .state
+        .in_bounds(&Variant::Standard.square("c3").unwrap()));
+    assert_eq!(game.current_player().royal, Some(PieceKind::Queen));
+}
+
+#[test]
+fn a_variant_starts_the_same_from_its_setup() {
+    let names = "standard capablanca gothic grand crazyhouse bughouse atomic threecheck \
+        kingofthehill racingkings antichess horde fogofwar duck xiangqi shogi hexagonal \
+        raumschach makruk sittuyin";
+    for variant in names
+        .split_whitespace()
+        .map(|name| Variant::from_name(name).unwrap())
+    {
+        let from_setup = Game::new(&Setup::from_variant(variant));
+        let from_fen = Game::from_variant(variant);
+        // the board holds which pieces have moved, and so which may castle or double move
+        let (pieces, expected) = (&from_setup.state.board.0 .0, &from_fen.state.board.0 .0);
+        assert_eq!(pieces, expected, "{variant:?}");
+        assert_eq!(
+            from_setup.move_list(&from_setup.state),
+            from_fen.move_list(&from_fen.state),
+            "{variant:?}"
+        );
+    }
+}
+
+fn setup(text: &str) -> Game {
+    Game::new(&Setup::parse(text).unwrap())
+}
+
+#[test]
+fn setups_carry_castling_rights_moved_pieces_and_en_passant() {
+    let position = "placement r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R";
+    assert!(setup(position).play_san("O-O-O").is_ok());
+    let rights = format!("{position}\nrights Kk");
+    assert!(setup(&rights).play_san("O-O").is_ok());
+    assert!(setup(&rights).play_san("O-O-O").is_err());
+    let moved = format!("{position}\nmoved h1");
+    assert!(setup(&moved).play_san("O-O").is_err());
+    assert!(setup(&moved).play_san("O-O-O").is_ok());
+
+    // P off their starting ranks can't double move
+    assert!(setup("placement 4k3/8/8/8/8/P7/8/4K3")
+        .play_san("a5")
+        .is_err());
+    let passant = "placement 4k3/8/8/3pP3/8/8/8/4K3";
+    assert!(setup(passant).play_san("exd6").is_err());
+    let mut game = setup(&format!("{passant}\npassant d6"));
+    game.play_san("exd6").unwrap();
+    assert!(game
+        .state
+        .get_piece(&Variant::Standard.square("d5").unwrap())
+        .is_none());
+    assert!(Setup::parse(&format!("{passant}\npassant e6")).is_err());
+}
+
+#[test]
+fn setups_name_what_is_wrong_with_them() {
+    let position = "placement 4k3/8/8/8/8/8/8/4K3\n";
+    let error = |line: &str| Setup::parse(&format!("{position}{line}")).err();
+    assert_eq!(
+        error("royal q"),
+        Some("the royal piece must be a single uppercase letter")
+    );
+    assert_eq!(
+        error("royal QK"),
+        Some("the royal piece must be a single uppercase letter")
+    );
+    assert_eq!(error("hole d4 d4"), Some("the same hole is listed twice"));
+    assert_eq!(
+        error("hole d4\nhole d4"),
+        Some("the same hole is listed twice")
+    );
+    assert_eq!(error("hole j9"), Some("hole is off the board"));
+}
@@ -0,0 +1,25 @@
+use rust_chess::game::{Game, GameState};
+use rust_chess::piece::{PieceKind, PlayerId};
+use rust_chess::variant::Variant;
+
+#[test]
+fn captured_pieces_go_to_hand_unpromoted() {
+    let mut game = Game::from_variant(Variant::Shogi);
+    // the number of first moves, as counted by other engines
+    assert_eq!(game.move_list(&game.state).len(), 30);
+    for san in "c4 g6 Bxh8=+ Sxh8".split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    let hand = |player_id| game.state.get_player(player_id).hand.clone();
+    assert_eq!(hand(PlayerId(1)), vec![PieceKind::Bishop]);
+    assert_eq!(hand(PlayerId(2)), vec![PieceKind::Bishop]);
+}
+
+#[test]
+fn a_pawn_cannot_be_dropped_onto_a_file_with_another_or_the_last_rank() {
+    let fen = "4k4/9/9/9/9/9/4P4/9/4K4[P] w - - 0 1";
+    let mut game = Game::from_state(GameState::from_fen(fen, Variant::Shogi).unwrap());
+    assert!(game.play_san("P@e5").is_err());
+    assert!(game.play_san("P@d9").is_err());
+    assert!(game.play_san("P@d5").is_ok());
+}
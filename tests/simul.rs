@@ -0,0 +1,21 @@
+use rust_chess::input::Input;
+use rust_chess::piece::PlayerId;
+use rust_chess::simul::Simul;
+use rust_chess::variant::Variant;
+
+fn select(notation: &str) -> Input {
+    Input::Select(Variant::Standard.square(notation).unwrap())
+}
+
+#[test]
+fn the_giver_moves_on_to_the_next_board() {
+    let mut simul = Simul::new(Variant::Standard, 2, PlayerId(1));
+    simul.play(0, select("e2")).unwrap();
+    simul.play(0, select("e4")).unwrap();
+    assert_eq!(simul.current(), 1);
+    assert!(simul.play(0, select("e7")).is_ok());
+    assert!(simul.play(1, select("d2")).is_ok());
+    let saved = Simul::load(&simul.save()).unwrap();
+    assert_eq!(saved.current(), simul.current());
+    assert_eq!(saved.scoreboard(), simul.scoreboard());
+}
@@ -1,12 +1,35 @@
 // for some reason, rust doesnt support integration tests that just print output and compare to see
 // if it is correct. this seems like it would be a very common use case, and I don't understand why
 // cargo doesn't seem to support it. although its always possible i havent read enough of the book
-mod game;
-mod board;
-mod piece;
+// so this test runs itself a second time to print the boards, and compares what it printed
+// against test_basic_movement.out, which is regenerated with PRINT_BASIC_MOVEMENT=1
+use rust_chess::{board, game, setup};
+use std::env;
+use std::process::{self, Command};
+
+const PRINT: &str = "PRINT_BASIC_MOVEMENT";
 
 fn main() {
-    let mut game = game::Game::new();
+    if env::var_os(PRINT).is_some() {
+        return play();
+    }
+    let output = Command::new(env::current_exe().unwrap())
+        .env(PRINT, "1")
+        .output()
+        .unwrap();
+    let printed = String::from_utf8_lossy(&output.stdout);
+    let expected = include_str!("test_basic_movement.out");
+    if !output.status.success() || printed != expected {
+        eprintln!("test_basic_movement printed:\n{printed}");
+        eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+        eprintln!("which differs from test_basic_movement.out");
+        process::exit(1);
+    }
+    println!("test_basic_movement ... ok");
+}
+
+fn play() {
+    let mut game = game::Game::new(&setup::Setup::default());
     game.draw();
     let pos = board::Vector(5, 7);
     println!("{}", pos.to_notation().unwrap());
@@ -0,0 +1,75 @@
+use rust_chess::database::position_hash;
+use rust_chess::game::Game;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn play(game: &mut Game, moves: &str) {
+    for san in moves.split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+}
+
+fn ply(game: &Game) -> usize {
+    game.tree().ply(game.tree().current())
+}
+
+#[test]
+fn redo_replays_what_undo_took_back() {
+    let mut game = Game::default();
+    play(&mut game, "e4 e5 Nf3");
+    let hash = position_hash(&game.state);
+    assert!(game.undo());
+    assert!(game.undo());
+    assert_eq!(ply(&game), 1);
+    assert!(game.redo());
+    assert!(game.redo());
+    assert_eq!(position_hash(&game.state), hash);
+    assert!(!game.redo());
+}
+
+#[test]
+fn a_new_move_clears_the_redo_stack() {
+    let mut game = Game::default();
+    play(&mut game, "e4 e5 Nf3");
+    game.undo();
+    play(&mut game, "Nc3");
+    assert!(!game.redo());
+    assert_eq!(ply(&game), 3);
+}
+
+#[test]
+fn goto_stops_at_the_end_and_reaches_the_start() {
+    let mut game = Game::default();
+    play(&mut game, "e4 e5 Nf3 Nc6");
+    let start = position_hash(&Game::default().state);
+    assert!(game.goto(0));
+    assert_eq!(position_hash(&game.state), start);
+    assert!(game.goto(2));
+    assert_eq!(ply(&game), 2);
+    // there are only four moves to go forward to
+    assert!(!game.goto(9));
+    assert_eq!(ply(&game), 4);
+    assert!(game.goto(0));
+    assert!(!game.undo());
+}
+
+#[test]
+fn the_terminal_reads_counts_of_more_than_one_digit() {
+    // twelve halfmoves, each selecting a piece then where it goes
+    let moves = "e2 e4 e7 e5 g1 f3 b8 c6 f1 c4 g8 f6 d2 d3 f8 c5 c2 c3 d7 d6 b1 d2 a7 a6";
+    let commands = ["undo 12", "undo", "redo 10", "redo 3", "quit"];
+    let input = moves.split_whitespace().chain(commands).collect::<Vec<_>>();
+    let mut terminal = Command::new(env!("CARGO_BIN_EXE_rust-chess"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = terminal.stdin.take().unwrap();
+    writeln!(stdin, "{}", input.join("\n")).unwrap();
+    drop(stdin);
+    let output = String::from_utf8(terminal.wait_with_output().unwrap().stdout).unwrap();
+    // all twelve moves are taken back at once, so the next undo has nothing left
+    assert_eq!(output.matches("no more moves to undo").count(), 1);
+    // ten of the twelve are replayed, so only two of the next three can be
+    assert_eq!(output.matches("no more moves to redo").count(), 1);
+}
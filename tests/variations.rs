@@ -0,0 +1,82 @@
+use rust_chess::game::Game;
+use rust_chess::tree::MoveTree;
+
+fn play(game: &mut Game, moves: &str) {
+    for san in moves.split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+}
+
+// the moves played from the root's children, main line first
+fn first_moves(game: &Game) -> Vec<String> {
+    let tree = game.tree();
+    let root = tree.node(MoveTree::ROOT).unwrap();
+    let children = root.children.iter().map(|id| tree.node(*id).unwrap());
+    children.filter_map(|node| node.san.clone()).collect()
+}
+
+#[test]
+fn a_move_after_a_rewind_starts_a_variation() {
+    let mut game = Game::default();
+    play(&mut game, "e4 e5 Nf3");
+    game.rewind(3);
+    play(&mut game, "d4");
+    assert_eq!(first_moves(&game), ["e4", "d4"]);
+    assert_eq!(game.tree().mainline().len(), 4);
+
+    // playing a move already tried follows it rather than adding it again
+    game.rewind(1);
+    play(&mut game, "e4");
+    assert_eq!(first_moves(&game), ["e4", "d4"]);
+}
+
+#[test]
+fn promoting_a_variation_makes_it_the_main_line() {
+    let mut game = Game::default();
+    play(&mut game, "e4 e5");
+    game.rewind(1);
+    play(&mut game, "c5 Nf3");
+    assert!(game.promote_variation());
+    let tree = game.tree();
+    let mainline: Vec<_> = tree.mainline()[1..]
+        .iter()
+        .map(|id| tree.node(*id).unwrap().san.clone().unwrap())
+        .collect();
+    assert_eq!(mainline, ["e4", "c5", "Nf3"]);
+    assert!(
+        game.pgn().contains("1. e4 c5 (1... e5) 2. Nf3"),
+        "{}",
+        game.pgn()
+    );
+}
+
+#[test]
+fn deleting_a_variation_removes_every_move_in_it() {
+    let mut game = Game::default();
+    play(&mut game, "e4 e5");
+    game.rewind(2);
+    play(&mut game, "d4 d5");
+    game.back();
+    assert!(game.delete_variation());
+    assert_eq!(first_moves(&game), ["e4"]);
+    assert_eq!(game.tree().current(), MoveTree::ROOT);
+    assert!(!game.pgn().contains("d4"));
+    // the root has no move leading to it to delete
+    assert!(!game.delete_variation());
+}
+
+#[test]
+fn nested_variations_are_written_inside_their_parent() {
+    let mut game = Game::default();
+    play(&mut game, "e4 e5 Nf3");
+    game.rewind(2);
+    play(&mut game, "c5 Nf3 d6");
+    game.rewind(2);
+    play(&mut game, "Nc3");
+    assert!(
+        game.pgn()
+            .contains("1. e4 e5 (1... c5 2. Nf3 (2. Nc3) 2... d6) 2. Nf3"),
+        "{}",
+        game.pgn()
+    );
+}
@@ -0,0 +1,45 @@
+use rust_chess::game::{Game, GameState};
+use rust_chess::outcome::Outcome;
+use rust_chess::piece::PlayerId;
+use rust_chess::variant::Variant;
+
+fn play(mut game: Game, moves: &str) -> Game {
+    for san in moves.split_whitespace() {
+        game.play_san(san).unwrap();
+    }
+    game
+}
+
+#[test]
+fn the_third_check_wins() {
+    let game = Game::from_variant(Variant::ThreeCheck);
+    let game = play(game, "e4 e5 Bc4 Nc6 Bxf7+ Kxf7 Qh5+ g6");
+    assert_eq!(game.state.outcome, None);
+    assert_eq!(
+        play(game, "Qxg6+").state.outcome,
+        Some(Outcome::Win(PlayerId(1), "three checks"))
+    );
+}
+
+#[test]
+fn a_king_in_the_centre_wins() {
+    let game = Game::from_variant(Variant::KingOfTheHill);
+    assert_eq!(
+        play(game, "e3 e6 Ke2 Ke7 Kd3 Kd6 Kd4").state.outcome,
+        Some(Outcome::Win(PlayerId(1), "king of the hill"))
+    );
+}
+
+#[test]
+fn racing_kings_lets_the_second_king_follow() {
+    let game = Game::from_variant(Variant::RacingKings);
+    assert_eq!(game.move_list(&game.state).len(), 21);
+
+    let state = GameState::from_fen("8/1K4k1/8/8/8/8/8/8 w - - 0 1", Variant::RacingKings);
+    let game = play(Game::from_state(state.unwrap()), "Kb8");
+    assert_eq!(game.state.outcome, None);
+    assert_eq!(
+        play(game, "Kg8").state.outcome,
+        Some(Outcome::Draw("both kings reaching the last rank"))
+    );
+}
@@ -0,0 +1,17 @@
+use rust_chess::game::Game;
+use rust_chess::piece::PieceKind;
+use rust_chess::variant::Variant;
+
+#[test]
+fn the_cannon_captures_over_a_screen() {
+    let mut game = Game::from_variant(Variant::Xiangqi);
+    // the number of first moves, as counted by other engines
+    assert_eq!(game.move_list(&game.state).len(), 44);
+    assert!(game.play_san("Cb9").is_err());
+    game.play_san("Cxb10").unwrap();
+    let b10 = Variant::Xiangqi.square("b10").unwrap();
+    assert_eq!(
+        game.state.get_piece(&b10).unwrap().kind,
+        PieceKind::Custom('C')
+    );
+}